@@ -0,0 +1,768 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::scenario::{
+    card::Card,
+    code::Code,
+    constraint::{Constraint, ConstraintID},
+    domain::Domain,
+    rule::{Expr, Op, Pattern, Rule},
+};
+
+/// A disagreement found in the card catalogue.
+pub enum CardIssue {
+    /// The name of the constraint is not understood.
+    Unparsed {
+        constraint: Constraint,
+        error: &'static str,
+    },
+    /// The name and the rule of the constraint disagree on a code.
+    Mismatch {
+        constraint: Constraint,
+        named: Rule,
+        code: Code,
+    },
+    /// The constraint disagrees with the printed card on an example code.
+    Example {
+        constraint: Constraint,
+        code: Code,
+        accepted: bool,
+    },
+    /// Two constraints of a card that should exclude
+    /// each other both accept a code.
+    Overlap {
+        first: ConstraintID,
+        second: ConstraintID,
+        code: Code,
+    },
+}
+
+impl std::fmt::Display for CardIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardIssue::Unparsed { constraint, error } => {
+                write!(f, "{} \"{}\": {error}", constraint.id, constraint.name)
+            }
+            CardIssue::Mismatch {
+                constraint,
+                named,
+                code,
+            } => write!(
+                f,
+                "{} \"{}\" reads as {named} but checks {} ({} disagrees)",
+                constraint.id, constraint.name, constraint.rule, code
+            ),
+            CardIssue::Example {
+                constraint,
+                code,
+                accepted,
+            } => write!(
+                f,
+                "{} \"{}\" should {} {code}",
+                constraint.id,
+                constraint.name,
+                if *accepted { "accept" } else { "reject" }
+            ),
+            CardIssue::Overlap {
+                first,
+                second,
+                code,
+            } => write!(f, "{first} and {second} both accept {code}"),
+        }
+    }
+}
+
+impl std::fmt::Debug for CardIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+/// The cards a game may be played with: the cards printed in the game,
+/// and cards loaded from card files, which replace printed cards with
+/// the same number.
+#[derive(Clone, Debug, Default)]
+pub struct Catalogue {
+    loaded: BTreeMap<u8, Card>,
+}
+
+impl Catalogue {
+    /// The cards printed in the game.
+    pub fn new() -> Self {
+        Catalogue::default()
+    }
+
+    pub(crate) fn card(&self, number: u8) -> Option<Card> {
+        match self.loaded.get(&number) {
+            Some(card) => Some(card.clone()),
+            None => Card::try_from(number).ok(),
+        }
+    }
+
+    /// The constraints of the card with the provided number.
+    pub fn constraints(&self, number: u8) -> Option<Vec<Constraint>> {
+        self.card(number).map(|card| card.constraints)
+    }
+
+    /// Numbers of the cards in the catalogue, in order.
+    pub fn numbers(&self) -> impl Iterator<Item = u8> + '_ {
+        Card::all()
+            .map(|card| card.num)
+            .merge(self.loaded.keys().copied())
+            .dedup()
+    }
+}
+
+/// Codes read off the printed cards, with the constraints of the
+/// card that accept them. These come from the card wording rather
+/// than from the rules, so that a wrong rule and a matching misreading
+/// of its name cannot agree.
+const EXAMPLES: &[(u8, &str, &[u8])] = &[
+    (1, "123", &[0]),
+    (1, "215", &[1]),
+    (2, "245", &[0]),
+    (2, "311", &[1]),
+    (2, "412", &[2]),
+    (3, "424", &[0]),
+    (3, "231", &[1]),
+    (3, "151", &[2]),
+    (4, "131", &[0]),
+    (4, "545", &[1]),
+    (4, "151", &[2]),
+    (5, "213", &[0]),
+    (5, "524", &[1]),
+    (6, "123", &[0]),
+    (6, "131", &[1]),
+    (7, "114", &[0]),
+    (7, "225", &[1]),
+    (8, "234", &[0]),
+    (8, "145", &[1]),
+    (8, "511", &[2]),
+    (8, "111", &[3]),
+    (9, "124", &[0]),
+    (9, "313", &[2]),
+    (9, "333", &[3]),
+    (10, "245", &[1]),
+    (10, "414", &[2]),
+    (11, "123", &[0]),
+    (11, "224", &[1]),
+    (11, "321", &[2]),
+    (12, "132", &[0]),
+    (12, "434", &[1]),
+    (12, "521", &[2]),
+    (13, "513", &[0]),
+    (13, "144", &[1]),
+    (13, "152", &[2]),
+    (14, "234", &[0]),
+    (14, "312", &[1]),
+    (14, "451", &[2]),
+    (14, "113", &[]),
+    (15, "523", &[0]),
+    (15, "152", &[1]),
+    (15, "125", &[2]),
+    (15, "553", &[]),
+    (16, "244", &[0]),
+    (16, "123", &[1]),
+    (16, "135", &[1]),
+    (17, "135", &[0]),
+    (17, "123", &[1]),
+    (17, "241", &[2]),
+    (17, "244", &[3]),
+    (18, "123", &[0]),
+    (18, "124", &[1]),
+    (19, "124", &[0]),
+    (19, "241", &[1]),
+    (19, "451", &[2]),
+    (20, "222", &[0]),
+    (20, "121", &[1]),
+    (20, "123", &[2]),
+    (21, "121", &[0]),
+    (21, "123", &[1]),
+    (22, "135", &[0]),
+    (22, "531", &[1]),
+    (22, "132", &[2]),
+    (23, "113", &[0]),
+    (23, "222", &[1]),
+    (23, "345", &[2]),
+    (24, "123", &[0]),
+    (24, "345", &[0]),
+    (24, "454", &[1]),
+    (24, "124", &[1]),
+    (24, "135", &[2]),
+    (24, "321", &[2]),
+    (25, "123", &[2]),
+    (25, "321", &[2]),
+    (25, "124", &[1]),
+    (25, "214", &[1]),
+    (25, "135", &[0]),
+    (25, "111", &[0]),
+    (26, "123", &[0, 1]),
+    (26, "442", &[2]),
+    (26, "345", &[]),
+    (27, "345", &[0]),
+    (27, "135", &[0, 1]),
+    (27, "444", &[]),
+    (28, "111", &[0, 1, 2]),
+    (28, "231", &[2]),
+    (29, "313", &[0, 2]),
+    (29, "232", &[1]),
+    (30, "414", &[0, 2]),
+    (30, "141", &[1]),
+    (31, "111", &[]),
+    (31, "121", &[1]),
+    (31, "222", &[0, 1, 2]),
+    (33, "123", &[1, 3, 5]),
+    (33, "244", &[0, 1, 2]),
+    (34, "123", &[0]),
+    (34, "112", &[0, 1]),
+    (34, "222", &[0, 1, 2]),
+    (34, "321", &[2]),
+    (35, "321", &[0]),
+    (35, "331", &[0, 1]),
+    (35, "123", &[2]),
+    (36, "111", &[0]),
+    (36, "112", &[1]),
+    (36, "113", &[2]),
+    (36, "444", &[0, 1]),
+    (36, "555", &[0, 2]),
+    (37, "132", &[0]),
+    (37, "153", &[1]),
+    (37, "513", &[2]),
+    (37, "222", &[0, 1, 2]),
+    (38, "335", &[0]),
+    (38, "145", &[1]),
+    (38, "133", &[2]),
+    (39, "123", &[0, 3, 5]),
+    (39, "211", &[1, 2, 4]),
+    (40, "135", &[0, 4, 8]),
+    (40, "333", &[1, 4, 7]),
+    (41, "345", &[0, 4, 8]),
+    (41, "444", &[1, 4, 7]),
+    (42, "123", &[0, 5]),
+    (42, "312", &[1, 3]),
+    (42, "222", &[]),
+    (43, "123", &[0, 3]),
+    (43, "221", &[1, 5]),
+    (43, "313", &[2, 4]),
+    (44, "123", &[2, 3]),
+    (44, "221", &[1, 5]),
+    (44, "313", &[0, 3]),
+    (45, "123", &[1, 4]),
+    (45, "113", &[2, 4]),
+    (45, "245", &[0, 3]),
+    (46, "334", &[2, 4]),
+    (46, "125", &[0, 3]),
+    (47, "141", &[2, 4]),
+    (47, "235", &[0, 3]),
+    (48, "123", &[0, 3, 6]),
+    (48, "221", &[1, 5, 8]),
+];
+
+impl Catalogue {
+    /// Check every card of the catalogue: each constraint name must read
+    /// as the rule it checks, the printed cards must agree with the
+    /// examples read off them, and the constraints of exclusive cards
+    /// must not accept the same code.
+    pub fn check(&self) -> Vec<CardIssue> {
+        let examples = EXAMPLES
+            .iter()
+            .filter_map(|&(number, code, accepting)| {
+                let card = Card::try_from(number).ok()?;
+                let code: Code = code.parse().ok()?;
+                Some(check_example(card, code, accepting))
+            })
+            .flatten();
+        self.numbers()
+            .filter_map(|number| self.card(number))
+            .flat_map(|card| {
+                let mismatches = card
+                    .constraints
+                    .iter()
+                    .filter_map(check_constraint)
+                    .collect_vec();
+                let overlaps = if card.exclusive {
+                    overlaps(&card.constraints)
+                } else {
+                    vec![]
+                };
+                mismatches.into_iter().chain(overlaps)
+            })
+            .chain(examples)
+            .collect()
+    }
+}
+
+fn check_constraint(constraint: &Constraint) -> Option<CardIssue> {
+    let named = match parse_name(&constraint.name) {
+        Ok(named) => named,
+        Err(error) => {
+            return Some(CardIssue::Unparsed {
+                constraint: constraint.clone(),
+                error,
+            });
+        }
+    };
+    Domain::default()
+        .codes()
+        .find(|code| named.eval(code) != constraint.verify(code))
+        .map(|code| CardIssue::Mismatch {
+            constraint: constraint.clone(),
+            named,
+            code,
+        })
+}
+
+fn check_example(card: Card, code: Code, accepting: &[u8]) -> Vec<CardIssue> {
+    card.constraints
+        .into_iter()
+        .filter_map(|constraint| {
+            let accepted = accepting.contains(&constraint.id.idx);
+            (constraint.verify(&code) != accepted).then_some(CardIssue::Example {
+                constraint,
+                code,
+                accepted,
+            })
+        })
+        .collect()
+}
+
+fn overlaps(constraints: &[Constraint]) -> Vec<CardIssue> {
+    constraints
+        .iter()
+        .tuple_combinations()
+        .filter_map(|(first, second)| {
+            Domain::default()
+                .codes()
+                .find(|code| first.verify(code) && second.verify(code))
+                .map(|code| CardIssue::Overlap {
+                    first: first.id,
+                    second: second.id,
+                    code,
+                })
+        })
+        .collect()
+}
+
+/// Why a card file cannot be loaded.
+pub enum CardFileError {
+    /// The file is not a valid list of cards.
+    Syntax(String),
+    /// The card has no constraints.
+    Empty(u8),
+    /// The name of a constraint is not understood.
+    Unparsed {
+        id: ConstraintID,
+        name: String,
+        error: &'static str,
+    },
+}
+
+impl std::fmt::Display for CardFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardFileError::Syntax(error) => write!(f, "Invalid card file: {error}"),
+            CardFileError::Empty(card) => write!(f, "Card {card} has no constraints"),
+            CardFileError::Unparsed { id, name, error } => write!(f, "{id} \"{name}\": {error}"),
+        }
+    }
+}
+
+impl std::fmt::Debug for CardFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CardFile {
+    #[serde(default, rename = "card")]
+    cards: Vec<CardEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CardEntry {
+    number: u8,
+    constraints: Vec<String>,
+    #[serde(default)]
+    exclusive: bool,
+}
+
+impl Catalogue {
+    /// Add the cards of a TOML card file to the catalogue, returning their
+    /// numbers. Each card lists the names of its constraints, which are
+    /// read with [`parse_name`]:
+    ///
+    /// ```toml
+    /// [[card]]
+    /// number = 49
+    /// constraints = ["🔷 < 🟨", "🔷 = 🟨", "🔷 > 🟨"]
+    /// exclusive = true
+    /// ```
+    ///
+    /// Cards are not exclusive unless marked so. A loaded card replaces
+    /// any card with the same number. Either every card of the file is
+    /// loaded or none is.
+    pub fn load(&mut self, source: &str) -> Result<Vec<u8>, CardFileError> {
+        let file: CardFile =
+            toml::from_str(source).map_err(|err| CardFileError::Syntax(err.message().into()))?;
+        let mut cards = vec![];
+        for entry in file.cards {
+            if entry.constraints.is_empty() {
+                return Err(CardFileError::Empty(entry.number));
+            }
+            let constraints = entry
+                .constraints
+                .into_iter()
+                .enumerate()
+                .map(|(idx, name)| match parse_name(&name) {
+                    Ok(rule) => Ok((name, rule)),
+                    Err(error) => Err(CardFileError::Unparsed {
+                        id: ConstraintID {
+                            card: entry.number,
+                            idx: idx as u8,
+                        },
+                        name,
+                        error,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            cards.push((entry.number, constraints, entry.exclusive));
+        }
+        Ok(cards
+            .into_iter()
+            .map(|(number, constraints, exclusive)| {
+                let card = Card::custom(number, constraints, exclusive);
+                self.loaded.insert(number, card);
+                number
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Token {
+    Colour(u8),
+    Compare(Op),
+    Plus,
+    And,
+    Number(u8),
+    Word(&'static str),
+}
+
+/// Words that may appear in constraint names, in lowercase.
+const WORDS: &[&str] = &[
+    "no",
+    "a",
+    "one",
+    "two",
+    "three",
+    "is",
+    "even",
+    "odd",
+    "smallest",
+    "largest",
+    "sum",
+    "multiple",
+    "of",
+    "#",
+    "triple",
+    "double",
+    "repetition",
+    "pair",
+    "pairs",
+    "ascending",
+    "descending",
+    "order",
+    "in",
+    "asc",
+    "asc/dsc",
+    "sequence",
+];
+
+fn tokenize(name: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = vec![];
+    let mut rest = name.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '<' | '>' if rest[1..].starts_with('=') => 2,
+            '🔷' | '🟨' | '🟣' | '<' | '>' | '=' | '+' | '&' => c.len_utf8(),
+            _ => rest
+                .find(|c: char| c.is_whitespace() || "🔷🟨🟣<>=+&".contains(c))
+                .unwrap_or(rest.len()),
+        };
+        let token = &rest[..len];
+        tokens.push(match token {
+            "🔷" | "B" | "blue" => Token::Colour(0),
+            "🟨" | "Y" | "yellow" => Token::Colour(1),
+            "🟣" | "P" | "purple" => Token::Colour(2),
+            "<" => Token::Compare(Op::Lt),
+            "<=" => Token::Compare(Op::Le),
+            "=" => Token::Compare(Op::Eq),
+            ">=" => Token::Compare(Op::Ge),
+            ">" => Token::Compare(Op::Gt),
+            "+" => Token::Plus,
+            "&" => Token::And,
+            "#s" => Token::Word("#"),
+            _ => match token.trim_end_matches('s').parse() {
+                Ok(n) => Token::Number(n),
+                Err(_) => Token::Word(
+                    WORDS
+                        .iter()
+                        .find(|word| word.eq_ignore_ascii_case(token))
+                        .ok_or("Unknown word in constraint name")?,
+                ),
+            },
+        });
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// The number a counting word stands for, e.g. `Two` in `Two 1s`.
+fn count(token: Token) -> Option<u8> {
+    match token {
+        Token::Number(n) => Some(n),
+        Token::Word("no") => Some(0),
+        Token::Word("a" | "one") => Some(1),
+        Token::Word("two") => Some(2),
+        Token::Word("three") => Some(3),
+        _ => None,
+    }
+}
+
+fn counted(expr: Expr, op: Op, n: u8) -> Rule {
+    Rule::Compare(expr, op, Expr::Value(n))
+}
+
+/// Codes whose longest run of digits going up by one, or when `down`
+/// is set also down by one, has `len` digits, as in `123` or `321`.
+fn sequence(len: u8, down: bool) -> Option<Rule> {
+    let steps: &'static [i8] = if down { &[1, -1] } else { &[1] };
+    let runs: Arc<[Rule]> = steps
+        .iter()
+        .map(|&step| Rule::All(Pattern::Difference(step)))
+        .collect();
+    let pairs: Arc<[Rule]> = steps
+        .iter()
+        .map(|&step| counted(Expr::Count(Pattern::Difference(step)), Op::Ge, 1))
+        .collect();
+    match len {
+        0 => Some(Rule::Not(Rule::Or(pairs).into())),
+        2 => Some(Rule::And(
+            [Rule::Not(Rule::Or(runs).into()), Rule::Or(pairs)].into(),
+        )),
+        3 => Some(Rule::Or(runs)),
+        _ => None,
+    }
+}
+
+/// Names starting with a count, e.g. `Two 3s` or `No order`.
+fn parse_count(n: u8, rest: &[Token]) -> Option<Rule> {
+    use Token::*;
+    match rest {
+        [Number(value)] => Some(counted(Expr::Count(Pattern::Value(*value)), Op::Eq, n)),
+        [Word("even"), Word("#")] => Some(counted(Expr::Count(Pattern::Even), Op::Eq, n)),
+        [Word("pair")] if n == 1 => Some(counted(Expr::Count(Pattern::Pair(Op::Eq)), Op::Eq, 1)),
+        [Word("pairs" | "repetition")] if n == 0 => Some(Rule::All(Pattern::Pair(Op::Ne))),
+        [Word("order")] if n == 0 => Some(Rule::Not(
+            Rule::Or(
+                [
+                    Rule::All(Pattern::Step(Op::Lt)),
+                    Rule::All(Pattern::Step(Op::Gt)),
+                ]
+                .into(),
+            )
+            .into(),
+        )),
+        [Word(run @ ("asc" | "asc/dsc")), Word("sequence")] if n == 0 => {
+            sequence(0, *run == "asc/dsc")
+        }
+        [
+            Word("in"),
+            Word(run @ ("asc" | "asc/dsc")),
+            Word("sequence"),
+        ] => sequence(n, *run == "asc/dsc"),
+        _ => None,
+    }
+}
+
+/// The strict comparison of a colour against both other colours.
+fn extreme(colour: u8, op: Op) -> Rule {
+    Rule::And(
+        (0..3)
+            .filter(|&other| other != colour)
+            .map(|other| Rule::Compare(Expr::Digit(colour), op, Expr::Digit(other)))
+            .collect(),
+    )
+}
+
+/// The positions of two colours, for [`Expr::Sum`].
+fn pair(a: u8, b: u8) -> &'static [u8] {
+    const PAIRS: [[u8; 2]; 9] = [
+        [0, 0],
+        [0, 1],
+        [0, 2],
+        [1, 0],
+        [1, 1],
+        [1, 2],
+        [2, 0],
+        [2, 1],
+        [2, 2],
+    ];
+    &PAIRS[usize::from(a * 3 + b)]
+}
+
+/// Read the rule a constraint name describes, e.g. `🔷 + 🟨 < 6`
+/// or `Two 3s`. Colours may be written as on the cards, as
+/// initials or as English words.
+pub fn parse_name(name: &str) -> Result<Rule, &'static str> {
+    use Token::*;
+    let tokens = tokenize(name)?;
+    if let [first, rest @ ..] = tokens.as_slice()
+        && let Some(n) = count(*first)
+        && let Some(rule) = parse_count(n, rest)
+    {
+        return Ok(rule);
+    }
+    let digit = |c: &u8| Expr::Digit(*c);
+    Ok(match tokens.as_slice() {
+        [Colour(c), Compare(op), Number(n)] => counted(digit(c), *op, *n),
+        [Colour(a), Compare(op), Colour(b)] => Rule::Compare(digit(a), *op, digit(b)),
+        [Colour(a), Plus, Colour(b), Compare(op), Number(n)] => {
+            counted(Expr::Sum(pair(*a, *b)), *op, *n)
+        }
+        [Colour(a), Compare(op), Colour(b), And, Colour(c)] => Rule::And(
+            [
+                Rule::Compare(digit(a), *op, digit(b)),
+                Rule::Compare(digit(a), *op, digit(c)),
+            ]
+            .into(),
+        ),
+        [Colour(c), Word("is"), Word(property)] | [Colour(c), Word(property)] => match *property {
+            "even" => Rule::Even(digit(c)),
+            "odd" => Rule::Odd(digit(c)),
+            "smallest" => extreme(*c, Op::Lt),
+            "largest" => extreme(*c, Op::Gt),
+            _ => return Err("Unknown property of a colour"),
+        },
+        [Word("sum"), Word("even")] => Rule::Even(Expr::Total),
+        [Word("sum"), Word("odd")] => Rule::Odd(Expr::Total),
+        [Word("sum"), Compare(op), Number(n)] => counted(Expr::Total, *op, *n),
+        [Word("sum"), Word("multiple"), Word("of"), Number(0)] => {
+            return Err("A sum cannot be a multiple of 0");
+        }
+        [Word("sum"), Word("multiple"), Word("of"), Number(n)] => Rule::MultipleOf(Expr::Total, *n),
+        [Word("even"), Compare(op), Word("odd")] => {
+            Rule::Compare(Expr::Count(Pattern::Even), *op, Expr::Count(Pattern::Odd))
+        }
+        [Word("odd"), Compare(op), Word("even")] => {
+            Rule::Compare(Expr::Count(Pattern::Odd), *op, Expr::Count(Pattern::Even))
+        }
+        [Word("triple"), Word("#")] => counted(Expr::Repeats, Op::Ge, 3),
+        [Word("double"), Word("#")] => counted(Expr::Repeats, Op::Eq, 2),
+        [Word("ascending")] => Rule::All(Pattern::Step(Op::Lt)),
+        [Word("descending")] => Rule::All(Pattern::Step(Op::Gt)),
+        _ => return Err("Unrecognised constraint name"),
+    })
+}
+
+#[test]
+fn catalogue_is_consistent() {
+    let issues = Catalogue::new().check();
+    assert!(issues.is_empty(), "{}", issues.iter().join("\n"));
+}
+
+#[test]
+fn check_reports_mislabelled_constraints() {
+    let constraint = |name: &str, rule| Constraint {
+        id: ConstraintID { card: 29, idx: 0 },
+        name: name.into(),
+        rule,
+        custom: false,
+    };
+    let issue = check_constraint(&constraint(
+        "🔷 = 1",
+        Rule::Compare(Expr::Digit(0), Op::Eq, Expr::Value(3)),
+    ))
+    .unwrap();
+    assert_eq!(
+        issue.to_string(),
+        "(29.0) \"🔷 = 1\" reads as B = 1 but checks B = 3 (111 disagrees)"
+    );
+    assert!(check_constraint(&constraint("Sum = 6", Rule::And([].into()))).is_some());
+    assert!(matches!(
+        check_constraint(&constraint("Gibberish", Rule::And([].into()))),
+        Some(CardIssue::Unparsed { .. })
+    ));
+    let mut card = Card::try_from(24).unwrap();
+    card.constraints[0].rule = Rule::All(Pattern::Step(Op::Lt));
+    let issues = check_example(card, "135".parse().unwrap(), &[2]);
+    assert_eq!(
+        issues.iter().join("\n"),
+        "(24.0) \"3 in asc sequence\" should reject 135"
+    );
+    assert!(
+        check_constraint(&constraint(
+            "Two 3s",
+            Rule::Compare(Expr::Count(Pattern::Value(3)), Op::Eq, Expr::Value(2))
+        ))
+        .is_none()
+    );
+}
+
+#[test]
+fn load_cards_from_toml() {
+    let mut catalogue = Catalogue::new();
+    let numbers = catalogue
+        .load(
+            r#"
+            [[card]]
+            number = 201
+            constraints = ["🔷 < 🟨", "B = Y", "blue > yellow"]
+            exclusive = true
+            "#,
+        )
+        .unwrap();
+    assert_eq!(numbers, vec![201]);
+    let card = catalogue.card(201).unwrap();
+    assert_eq!(&*card.constraints[1].name, "B = Y");
+    assert!(card.exclusive);
+    assert!(card.constraints[2].verify(&"312".parse().unwrap()));
+    assert!(catalogue.numbers().any(|number| number == 201));
+    assert!(Catalogue::new().card(201).is_none());
+
+    let bad = "[[card]]\nnumber = 202\nconstraints = [\"🔷 < 2\", \"Gibberish\"]";
+    assert_eq!(
+        catalogue.load(bad).unwrap_err().to_string(),
+        "(202.1) \"Gibberish\": Unknown word in constraint name"
+    );
+    assert!(catalogue.card(202).is_none());
+    let zero = "[[card]]\nnumber = 202\nconstraints = [\"Sum multiple of 0\"]";
+    assert_eq!(
+        catalogue.load(zero).unwrap_err().to_string(),
+        "(202.0) \"Sum multiple of 0\": A sum cannot be a multiple of 0"
+    );
+    let replaced = "[[card]]\nnumber = 3\nconstraints = [\"🟨 < 2\", \"🟨 >= 2\"]";
+    assert_eq!(catalogue.load(replaced).unwrap(), vec![3]);
+    assert_eq!(catalogue.constraints(3).unwrap().len(), 2);
+    let even = "[[card]]\nnumber = 5\nconstraints = [\"🔷 even\", \"🔷 odd\"]";
+    assert_eq!(catalogue.load(even).unwrap(), vec![5]);
+    let text = |catalogue: &Catalogue| {
+        catalogue.constraints(5).unwrap()[0]
+            .text(crate::render::Locale::Fr)
+            .to_owned()
+    };
+    assert_eq!(text(&catalogue), "🔷 even");
+    assert_eq!(text(&Catalogue::new()), "🔷 est pair");
+    assert_eq!(catalogue.numbers().filter(|&number| number == 3).count(), 1);
+    assert!(
+        catalogue
+            .load("[[card]]\nnumber = 203\nconstraints = []")
+            .is_err()
+    );
+    assert!(catalogue.load("number = 204").is_err());
+}
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, de::Error};
+
+use crate::{
+    catalogue::{CardFileError, Catalogue},
+    guess_sequence::strategy::StrategyName,
+    render::{Locale, Style},
+};
+
+/// Default options, read from `config.toml` in the `turing-solve`
+/// configuration directory:
+///
+/// ```toml
+/// format = "json"
+/// strategy = "balance"
+/// style = "ascii"
+/// locale = "fr"
+/// cards = "cards.toml"
+/// ```
+///
+/// Every option may be left out. Options given on the command
+/// line take precedence over the file.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Output format of `solve`, `tree` and the simulation report.
+    pub format: Option<OutputFormat>,
+    /// How guesses are chosen when building a decision tree.
+    pub strategy: Option<StrategyName>,
+    pub style: Option<Style>,
+    pub locale: Option<Locale>,
+    /// Card file to add to the catalogue, relative to the configuration file.
+    pub cards: Option<PathBuf>,
+}
+
+/// How a report is written out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("Format must be one of text or json"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// Why the configuration cannot be loaded.
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Syntax(PathBuf, String),
+    Cards(PathBuf, CardFileError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, error) => write!(f, "Cannot read {}: {error}", path.display()),
+            ConfigError::Syntax(path, error) => write!(f, "{}: {error}", path.display()),
+            ConfigError::Cards(path, error) => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::fmt::Debug for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl Config {
+    /// Where the configuration is looked for when no path is provided:
+    /// `$XDG_CONFIG_HOME/turing-solve/config.toml`, falling back to
+    /// `~/.config/turing-solve/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("turing-solve").join("config.toml"))
+    }
+
+    /// Read the configuration at a path.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| ConfigError::Read(path.to_path_buf(), err))?;
+        let mut config =
+            Config::parse(&source).map_err(|err| ConfigError::Syntax(path.to_path_buf(), err))?;
+        if let (Some(cards), Some(dir)) = (&config.cards, path.parent()) {
+            config.cards = Some(dir.join(cards));
+        }
+        Ok(config)
+    }
+
+    /// Read the configuration at the default path, if there is one.
+    pub fn load_default() -> Result<Config, ConfigError> {
+        match Config::default_path() {
+            Some(path) if path.exists() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    fn parse(source: &str) -> Result<Config, String> {
+        toml::from_str(source).map_err(|err| err.message().to_string())
+    }
+
+    /// The printed cards, with the cards of the configured card file.
+    pub fn catalogue(&self) -> Result<Catalogue, ConfigError> {
+        let mut catalogue = Catalogue::new();
+        if let Some(path) = &self.cards {
+            let source = std::fs::read_to_string(path)
+                .map_err(|err| ConfigError::Read(path.clone(), err))?;
+            catalogue
+                .load(&source)
+                .map_err(|err| ConfigError::Cards(path.clone(), err))?;
+        }
+        Ok(catalogue)
+    }
+}
+
+#[test]
+fn config_parse() {
+    let config = Config::parse(
+        "format = \"json\"\nstrategy = \"Minimax\"\nstyle = \"ascii\"\nlocale = \"fr\"\ncards = \"cards.toml\"",
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        Config {
+            format: Some(OutputFormat::Json),
+            strategy: Some(StrategyName::Minimax),
+            style: Some(Style::Ascii),
+            locale: Some(Locale::Fr),
+            cards: Some("cards.toml".into()),
+        }
+    );
+    assert_eq!(Config::parse(""), Ok(Config::default()));
+    assert!(Config::parse("style = \"bold\"").is_err());
+    assert!(Config::parse("strategy = \"greedy\"").is_err());
+    assert!(Config::parse("colour = \"blue\"").is_err());
+}
@@ -1,42 +1,89 @@
 use crate::{
-    guess_sequence::{Guess, solver::shrink},
-    scenario::code::Code,
+    guess_sequence::Guess,
+    scenario::{code::Code, constraint::ConstraintID},
     solver::Solution,
 };
 use itertools::Itertools;
 
+/// Indices, in ascending order, of the solutions
+/// that remain possible at some point of the tree.
+pub type Candidates = Vec<usize>;
+
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
-pub struct Decision<'a> {
-    pub guesses: Vec<Guess<'a>>,
-    pub current_guess: Guess<'a>,
+pub struct Decision {
+    pub current_guess: Guess,
+    pub remaining: Candidates,
     pub solution: Option<Code>,
 }
 
-impl<'a> Decision<'a> {
+impl Decision {
+    /// Answer `current_guess` when only `candidates` remain, or
+    /// `None` if that answer contradicts every candidate.
     pub fn prepare(
-        prior_guesses: Vec<Guess<'a>>,
-        current_guess: Guess<'a>,
-        available_solutions: &Vec<&Solution>,
-    ) -> Decision<'a> {
-        let guesses = prior_guesses
+        candidates: &[usize],
+        current_guess: Guess,
+        available_solutions: &[&Solution],
+    ) -> Option<Decision> {
+        let remaining = candidates
             .iter()
-            .cloned()
-            .chain(std::iter::once(current_guess))
+            .copied()
+            .filter(|&idx| current_guess.permits(available_solutions[idx]))
             .collect_vec();
-        let solutions = shrink(&available_solutions, &guesses);
-        Decision {
-            guesses: guesses,
-            current_guess: current_guess,
-            solution: if solutions.len() == 1 {
-                solutions.first().map(|s| s.code)
-            } else {
-                None
-            },
+        if remaining.is_empty() {
+            return None;
         }
+        let solution = forced_code(
+            &remaining
+                .iter()
+                .map(|&idx| available_solutions[idx])
+                .collect_vec(),
+        );
+        Some(Decision {
+            current_guess,
+            remaining,
+            solution,
+        })
     }
 }
 
-pub struct Branch<'a> {
-    pub verified: Option<Decision<'a>>,
-    pub unverified: Option<Decision<'a>>,
+/// The code shared by every provided solution, if they all agree.
+///
+/// Remaining solutions that only differ by their verifier
+/// interpretation need no further guesses.
+pub(crate) fn forced_code(solutions: &[&Solution]) -> Option<Code> {
+    solutions.iter().map(|s| s.code).dedup().exactly_one().ok()
+}
+
+pub struct Branch {
+    pub verified: Option<Decision>,
+    pub unverified: Option<Decision>,
+}
+
+impl Branch {
+    /// Both possible answers to `constraint` when only `candidates`
+    /// remain, omitting any answer that no candidate permits.
+    pub fn prepare(
+        candidates: &[usize],
+        constraint: ConstraintID,
+        available_solutions: &[&Solution],
+    ) -> Branch {
+        let answer = |verified| {
+            Decision::prepare(
+                candidates,
+                Guess {
+                    constraint,
+                    verified,
+                },
+                available_solutions,
+            )
+        };
+        Branch {
+            verified: answer(true),
+            unverified: answer(false),
+        }
+    }
+
+    pub fn decisions(&self) -> impl Iterator<Item = &Decision> {
+        [&self.verified, &self.unverified].into_iter().flatten()
+    }
 }
@@ -1,53 +1,605 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 use itertools::Itertools;
 use log::debug;
+use serde::Serialize;
 
-use crate::guess_sequence::{Branch, Decision};
+use crate::{
+    guess_sequence::{Branch, Candidates, Decision, Guess, Strategy},
+    render::{Render, Wording},
+    scenario::{
+        code::Code,
+        constraint::{Constraint, ConstraintID},
+    },
+    solver::Solution,
+};
 
-pub struct DecisionTree<'a> {
-    pub roots: Vec<Decision<'a>>,
-    pub decisions: HashMap<Decision<'a>, Branch<'a>>,
+pub struct DecisionTree {
+    pub solutions: Vec<Solution>,
+    /// The guess made for each set of remaining candidates,
+    /// shared by every guess sequence that leads to it.
+    pub decisions: HashMap<Candidates, Branch>,
+    /// Candidate sets that no guess can tell apart.
+    pub unresolved: HashSet<Candidates>,
+    /// The code, if it is known before any guess is made.
+    pub solution: Option<Code>,
+    /// The strategy the tree was built with, which also picks the
+    /// guesses for answers that leave the tree's paths.
+    pub(crate) strategy: Box<dyn Strategy + Send + Sync>,
 }
 
-impl<'a> std::fmt::Display for DecisionTree<'a> {
+/// A decision tree that cannot distinguish between all solutions.
+pub struct IncompleteTree {
+    pub tree: Box<DecisionTree>,
+}
+
+impl Render for IncompleteTree {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
+        writeln!(f, "Could not distinguish between all solutions:")?;
+        let paths = self.tree.guess_paths();
+        for candidates in self.tree.unresolved.iter().sorted() {
+            match paths.get(candidates) {
+                Some(guesses) if !guesses.is_empty() => writeln!(
+                    f,
+                    "After {}:",
+                    guesses.iter().map(|g| wording.show(g)).join(", ")
+                )?,
+                _ => writeln!(f, "Before any guess:")?,
+            }
+            for &idx in candidates {
+                writeln!(f, "\t{}", wording.show(&self.tree.solutions[idx]))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for IncompleteTree {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let roots = self
-            .roots
-            .iter()
-            .map(|d| convert_to_decision_tree_string(d, &self.decisions, 0))
-            .join("");
-        write!(f, "{}", roots)
-    }
-}
-
-fn convert_to_decision_tree_string(
-    decision: &Decision,
-    map: &HashMap<Decision, Branch>,
-    indent: usize,
-) -> String {
-    let children = map
-        .get(decision)
-        .map(|branch| {
-            [&branch.verified, &branch.unverified]
-                .into_iter()
-                .flatten()
-                .map(|d| convert_to_decision_tree_string(d, map, indent + 1))
-                .join("")
-        })
-        .unwrap_or(String::new());
-    if children.is_empty() && decision.solution == None {
-        debug!("Problem w: {:?}", decision)
-    }
-    format!(
-        "{}{} {}\n{}",
-        "\t".repeat(indent),
-        decision.current_guess,
-        if let Some(code) = decision.solution {
+        self.render(f, Wording::default())
+    }
+}
+
+impl std::fmt::Debug for IncompleteTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+/// Summary of the shape of a [`DecisionTree`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeStats {
+    pub depth: usize,
+    pub leaves: usize,
+    pub dead_ends: usize,
+    pub average_path_length: f64,
+    pub worst_case_path_length: usize,
+    pub leaf_depths: BTreeMap<usize, usize>,
+}
+
+impl std::fmt::Display for TreeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Depth: {}", self.depth)?;
+        writeln!(f, "Leaves: {} ({} dead ends)", self.leaves, self.dead_ends)?;
+        writeln!(
+            f,
+            "Path length: {:.2} average, {} worst case",
+            self.average_path_length, self.worst_case_path_length
+        )?;
+        writeln!(f, "Leaf depths:")?;
+        for (depth, count) in &self.leaf_depths {
+            writeln!(f, "\t{depth}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The leaves below a node of a [`DecisionTree`], and those
+/// among them identifying a solution, counted at each depth.
+#[derive(Clone, Default)]
+struct Shape {
+    leaves: BTreeMap<usize, usize>,
+    solutions: BTreeMap<usize, usize>,
+}
+
+impl Shape {
+    /// A leaf, with no guess left to make.
+    fn leaf(solution: bool) -> Self {
+        let mut shape = Shape::default();
+        shape.leaves.insert(0, 1);
+        if solution {
+            shape.solutions.insert(0, 1);
+        }
+        shape
+    }
+
+    /// Count the leaves of a subtree `depth` guesses further down.
+    fn add(&mut self, other: &Shape, depth: usize) {
+        for (ours, theirs) in [
+            (&mut self.leaves, &other.leaves),
+            (&mut self.solutions, &other.solutions),
+        ] {
+            for (d, count) in theirs {
+                *ours.entry(d + depth).or_insert(0) += count;
+            }
+        }
+    }
+
+    fn average_path_length(&self) -> f64 {
+        let count = self.solutions.values().sum::<usize>();
+        if count == 0 {
+            return 0.0;
+        }
+        let total = self.solutions.iter().map(|(d, c)| d * c).sum::<usize>();
+        total as f64 / count as f64
+    }
+}
+
+impl DecisionTree {
+    pub(super) fn into_result(self) -> Result<Self, IncompleteTree> {
+        if self.unresolved.is_empty() {
+            Ok(self)
+        } else {
+            Err(IncompleteTree {
+                tree: Box::new(self),
+            })
+        }
+    }
+
+    /// The shortest guess sequence leading to each set of candidates.
+    fn guess_paths(&self) -> HashMap<&Candidates, Vec<Guess>> {
+        let root = self.root();
+        let mut paths = HashMap::new();
+        let mut queue = VecDeque::from([(vec![], &root)]);
+        while let Some((guesses, candidates)) = queue.pop_front() {
+            let Some(branch) = self.decisions.get(candidates) else {
+                continue;
+            };
+            for decision in branch.decisions() {
+                if paths.contains_key(&decision.remaining) {
+                    continue;
+                }
+                let mut path: Vec<Guess> = guesses.clone();
+                path.push(decision.current_guess);
+                paths.insert(&decision.remaining, path.clone());
+                queue.push_back((path, &decision.remaining));
+            }
+        }
+        paths
+    }
+
+    /// The candidates before any guess is made.
+    pub fn root(&self) -> Candidates {
+        (0..self.solutions.len()).collect()
+    }
+
+    /// The answers to the first guess.
+    pub fn roots(&self) -> Vec<&Decision> {
+        self.decisions
+            .get(&self.root())
+            .map(|branch| branch.decisions().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every leaf of the tree with the number of guesses needed to
+    /// reach it, in depth-first order.
+    ///
+    /// Each set of candidates is visited once, so a leaf shared by
+    /// several guess sequences is listed once, at the first depth found.
+    pub fn leaves(&self) -> Vec<(usize, &Decision)> {
+        let mut leaves = vec![];
+        let mut visited = HashSet::new();
+        let mut stack = self.roots().into_iter().rev().map(|d| (1, d)).collect_vec();
+        while let Some((depth, decision)) = stack.pop() {
+            if !visited.insert(&decision.remaining) {
+                continue;
+            }
+            match self.decisions.get(&decision.remaining) {
+                Some(branch) => stack.extend(
+                    branch
+                        .decisions()
+                        .collect_vec()
+                        .into_iter()
+                        .rev()
+                        .map(|d| (depth + 1, d)),
+                ),
+                None => leaves.push((depth, decision)),
+            }
+        }
+        leaves
+    }
+
+    /// Leaves that neither identify a solution nor lead to further guesses.
+    pub fn dead_ends(&self) -> Vec<&Decision> {
+        self.leaves()
+            .into_iter()
+            .map(|(_, d)| d)
+            .filter(|d| d.solution.is_none())
+            .collect()
+    }
+
+    /// The leaves at each depth, counted once per guess sequence
+    /// leading to them. Each subtree is measured once, however
+    /// many sequences share it.
+    fn shape(&self) -> Shape {
+        fn below<'t>(
+            tree: &'t DecisionTree,
+            candidates: &'t Candidates,
+            shapes: &mut HashMap<&'t Candidates, Shape>,
+        ) -> Shape {
+            if let Some(shape) = shapes.get(candidates) {
+                return shape.clone();
+            }
+            let mut shape = Shape::default();
+            if let Some(branch) = tree.decisions.get(candidates) {
+                for decision in branch.decisions() {
+                    let child = match tree.decisions.contains_key(&decision.remaining) {
+                        true => below(tree, &decision.remaining, shapes),
+                        false => Shape::leaf(decision.solution.is_some()),
+                    };
+                    shape.add(&child, 1);
+                }
+            }
+            shapes.insert(candidates, shape.clone());
+            shape
+        }
+        below(self, &self.root(), &mut HashMap::new())
+    }
+
+    /// The number of guesses along the longest path of the tree.
+    pub fn depth(&self) -> usize {
+        self.shape().leaves.keys().max().copied().unwrap_or(0)
+    }
+
+    /// The mean number of guesses needed to reach a solution.
+    pub fn average_path_length(&self) -> f64 {
+        self.shape().average_path_length()
+    }
+
+    /// The largest number of guesses needed to reach a solution.
+    pub fn worst_case_path_length(&self) -> usize {
+        self.shape().solutions.keys().max().copied().unwrap_or(0)
+    }
+
+    /// The number of leaves at each depth.
+    pub fn leaf_depth_histogram(&self) -> BTreeMap<usize, usize> {
+        self.shape().leaves
+    }
+
+    pub fn stats(&self) -> TreeStats {
+        let shape = self.shape();
+        let leaves = shape.leaves.values().sum::<usize>();
+        TreeStats {
+            depth: shape.leaves.keys().max().copied().unwrap_or(0),
+            leaves,
+            dead_ends: leaves - shape.solutions.values().sum::<usize>(),
+            average_path_length: shape.average_path_length(),
+            worst_case_path_length: shape.solutions.keys().max().copied().unwrap_or(0),
+            leaf_depths: shape.leaves,
+        }
+    }
+
+    /// Render the tree printing each shared subtree once,
+    /// and referring back to it wherever it recurs.
+    pub fn compact(&self) -> CompactTree<'_> {
+        CompactTree { tree: self }
+    }
+
+    fn fmt_tree(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        wording: Wording,
+        labels: Option<&HashMap<&Candidates, usize>>,
+    ) -> std::fmt::Result {
+        if let Some(code) = self.solution {
+            return writeln!(f, "--- {}", code);
+        }
+        if self.unresolved.contains(&self.root()) {
+            return writeln!(f, "{}", self.unresolved_marker(&self.root()));
+        }
+        let mut printed = HashSet::new();
+        for decision in self.roots() {
+            self.fmt_decision(f, wording, decision, 0, labels, &mut printed)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_decision(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        wording: Wording,
+        decision: &Decision,
+        indent: usize,
+        labels: Option<&HashMap<&Candidates, usize>>,
+        printed: &mut HashSet<usize>,
+    ) -> std::fmt::Result {
+        let label = labels.and_then(|labels| labels.get(&decision.remaining));
+        let outcome = if let Some(code) = decision.solution {
             format!("--- {}", code)
+        } else if self.unresolved.contains(&decision.remaining) {
+            self.unresolved_marker(&decision.remaining)
         } else {
-            String::new()
-        },
-        children,
-    )
+            match label {
+                Some(label) if printed.contains(label) => {
+                    return writeln!(
+                        f,
+                        "{}{} => #{}",
+                        "\t".repeat(indent),
+                        self.describe(&decision.current_guess, wording),
+                        label
+                    );
+                }
+                Some(label) => format!("[#{}]", label),
+                None => String::new(),
+            }
+        };
+        writeln!(
+            f,
+            "{}{} {}",
+            "\t".repeat(indent),
+            self.describe(&decision.current_guess, wording),
+            outcome
+        )?;
+        if let Some(label) = label {
+            printed.insert(*label);
+        }
+        if let Some(branch) = self.decisions.get(&decision.remaining) {
+            for child in branch.decisions() {
+                self.fmt_decision(f, wording, child, indent + 1, labels, printed)?;
+            }
+        } else if decision.solution.is_none() {
+            debug!("Dead end: {:?}", decision)
+        }
+        Ok(())
+    }
+
+    /// The constraint with the provided id, as used by any solution.
+    pub fn constraint(&self, id: ConstraintID) -> Option<&Constraint> {
+        self.solutions
+            .iter()
+            .flat_map(|s| s.constraints.iter())
+            .find(|c| c.id == id)
+    }
+
+    /// The guess along with the name of its constraint.
+    fn describe(&self, guess: &Guess, wording: Wording) -> String {
+        match self.constraint(guess.constraint) {
+            Some(constraint) => format!(
+                "{}{}",
+                if guess.verified {
+                    ""
+                } else {
+                    wording.negation()
+                },
+                wording.show(constraint)
+            ),
+            None => wording.show(guess).to_string(),
+        }
+    }
+
+    fn unresolved_marker(&self, candidates: &Candidates) -> String {
+        format!(
+            "??? {}",
+            candidates
+                .iter()
+                .map(|&idx| self.solutions[idx].code)
+                .unique()
+                .join(" | ")
+        )
+    }
+
+    /// Identify each subtree by its structure, the guesses
+    /// it asks and the codes it leads to, so that subtrees
+    /// reached with different candidates but rendered
+    /// identically share an identifier.
+    fn subtree_ids(&self) -> HashMap<&Candidates, usize> {
+        fn intern<'t>(
+            tree: &'t DecisionTree,
+            candidates: &'t Candidates,
+            signatures: &mut HashMap<String, usize>,
+            ids: &mut HashMap<&'t Candidates, usize>,
+        ) -> String {
+            if let Some(id) = ids.get(candidates) {
+                return id.to_string();
+            }
+            let Some(branch) = tree.decisions.get(candidates) else {
+                return tree.unresolved_marker(candidates);
+            };
+            let signature = branch
+                .decisions()
+                .map(|d| {
+                    let outcome = match d.solution {
+                        Some(code) => code.to_string(),
+                        None => format!("#{}", intern(tree, &d.remaining, signatures, ids)),
+                    };
+                    format!("{:?}:{}", d.current_guess, outcome)
+                })
+                .join(",");
+            let next_id = signatures.len();
+            let id = *signatures.entry(signature).or_insert(next_id);
+            ids.insert(candidates, id);
+            id.to_string()
+        }
+        let mut signatures = HashMap::new();
+        let mut ids = HashMap::new();
+        let root = self.root();
+        for decision in self.roots() {
+            if decision.solution.is_none() {
+                intern(self, &decision.remaining, &mut signatures, &mut ids);
+            }
+        }
+        ids.remove(&root);
+        ids
+    }
+
+    /// Number each subtree that is printed more than once
+    /// in a compact rendering, in the order they are first printed.
+    fn shared_subtree_labels(&self) -> HashMap<&Candidates, usize> {
+        let ids = self.subtree_ids();
+        let mut seen = HashSet::new();
+        let mut first_printed = vec![];
+        let mut shared = HashSet::new();
+        let mut stack = self.roots().into_iter().rev().collect_vec();
+        while let Some(decision) = stack.pop() {
+            let Some(id) = ids.get(&decision.remaining) else {
+                continue;
+            };
+            if !seen.insert(id) {
+                shared.insert(id);
+                continue;
+            }
+            first_printed.push(id);
+            if let Some(branch) = self.decisions.get(&decision.remaining) {
+                stack.extend(branch.decisions().collect_vec().into_iter().rev());
+            }
+        }
+        let labels_by_id: HashMap<_, _> = first_printed
+            .into_iter()
+            .filter(|id| shared.contains(id))
+            .enumerate()
+            .map(|(idx, id)| (id, idx + 1))
+            .collect();
+        ids.iter()
+            .filter_map(|(&candidates, id)| labels_by_id.get(id).map(|label| (candidates, *label)))
+            .collect()
+    }
+}
+
+impl Render for DecisionTree {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
+        self.fmt_tree(f, wording, None)
+    }
+}
+
+impl std::fmt::Display for DecisionTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, Wording::default())
+    }
+}
+
+/// A [`DecisionTree`] rendering that prints shared subtrees once.
+pub struct CompactTree<'t> {
+    tree: &'t DecisionTree,
+}
+
+impl<'t> Render for CompactTree<'t> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
+        self.tree
+            .fmt_tree(f, wording, Some(&self.tree.shared_subtree_labels()))
+    }
+}
+
+impl<'t> std::fmt::Display for CompactTree<'t> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, Wording::default())
+    }
+}
+
+#[test]
+fn tree_stats() {
+    let tree = crate::guess_sequence::example_tree();
+    let stats = tree.stats();
+    assert_eq!(
+        stats,
+        TreeStats {
+            depth: 5,
+            leaves: 16,
+            dead_ends: 0,
+            average_path_length: 4.0625,
+            worst_case_path_length: 5,
+            leaf_depths: [(3, 1), (4, 13), (5, 2)].into(),
+        }
+    );
+    assert_eq!(tree.leaves().len(), stats.leaves);
+    assert_eq!(tree.depth(), stats.depth);
+    assert_eq!(tree.leaf_depth_histogram(), stats.leaf_depths);
+    assert_eq!(tree.average_path_length(), stats.average_path_length);
+    assert_eq!(tree.worst_case_path_length(), stats.worst_case_path_length);
+}
+
+#[test]
+fn single_solution_is_resolved() {
+    use crate::{guess_sequence::solver::guess_sequence, solver::*};
+    let solutions = turing_solve(
+        [(3, 0), (10, 0), (14, 0), (17, 2)]
+            .into_iter()
+            .map(|(card, idx)| constraints_for_card(card).unwrap()[idx].clone())
+            .collect(),
+    );
+    assert_eq!(solutions.len(), 1);
+    let tree = guess_sequence(&solutions).unwrap();
+    assert_eq!(tree.to_string(), "--- 122\n");
+}
+
+#[test]
+fn incomplete_tree_reports_guess_paths() {
+    use crate::{
+        guess_sequence::{solver::guess_sequence_with, strategy::Balance},
+        solver::example_solutions,
+    };
+    /// Gives up whenever two solutions are left.
+    struct GiveUpOnPairs;
+    impl Strategy for GiveUpOnPairs {
+        fn next_query<'a>(
+            &self,
+            queries: &[&'a Constraint],
+            remaining: &[&Solution],
+        ) -> Option<&'a Constraint> {
+            match remaining.len() {
+                2 => None,
+                _ => Balance.next_query(queries, remaining),
+            }
+        }
+    }
+    let Err(incomplete) = guess_sequence_with(&example_solutions(), GiveUpOnPairs) else {
+        panic!("the tree should be incomplete");
+    };
+    let unresolved = &incomplete.tree.unresolved;
+    assert!(!unresolved.is_empty());
+    assert!(unresolved.iter().all(|candidates| candidates.len() == 2));
+    let report = incomplete.to_string();
+    assert_eq!(report.matches("After ").count(), unresolved.len());
+    assert_eq!(report.lines().count(), 1 + unresolved.len() * 3, "{report}");
+}
+
+#[test]
+fn tree_only_asks_uncertain_questions() {
+    use crate::{
+        guess_sequence::{forced_code, solver::guess_sequence},
+        solver::*,
+    };
+    let solutions = turing_solve(
+        [3, 10, 14, 17, 40]
+            .into_iter()
+            .flat_map(|card| constraints_for_card(card).unwrap())
+            .collect(),
+    );
+    let tree = guess_sequence(&solutions).unwrap();
+    for (candidates, branch) in &tree.decisions {
+        let remaining = candidates.iter().map(|&idx| &solutions[idx]).collect_vec();
+        assert!(forced_code(&remaining).is_none());
+        assert!(branch.verified.is_some() && branch.unverified.is_some());
+    }
+    assert!(tree.dead_ends().is_empty());
+}
+
+#[test]
+fn compact_rendering_shares_subtrees() {
+    use crate::{guess_sequence::solver::guess_sequence, solver::*};
+    let solutions = turing_solve(
+        [40, 41, 10]
+            .into_iter()
+            .flat_map(|card| constraints_for_card(card).unwrap())
+            .collect(),
+    );
+    let tree = guess_sequence(&solutions).unwrap();
+    let full = tree.to_string();
+    let compact = tree.compact().to_string();
+    let labels = tree.shared_subtree_labels();
+    assert!(!labels.is_empty());
+    assert!(compact.contains("=> #"));
+    assert!(compact.lines().count() < full.lines().count());
+    for label in labels.values() {
+        assert_eq!(compact.matches(&format!("[#{}]", label)).count(), 1);
+    }
 }
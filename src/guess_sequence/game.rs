@@ -0,0 +1,206 @@
+use itertools::Itertools;
+
+use crate::{
+    guess_sequence::{DecisionTree, Guess, Position, solver::shrink},
+    scenario::{
+        code::Code,
+        constraint::{Constraint, ConstraintID},
+        domain::Domain,
+    },
+};
+
+/// A game in progress: the decision tree of the scenario
+/// and the answers received so far.
+pub struct Game {
+    tree: DecisionTree,
+    answers: Vec<Guess>,
+}
+
+/// A verifier card and whether each of its
+/// candidate constraints is still possible.
+pub struct VerifierState<'g> {
+    pub card: u8,
+    pub constraints: Vec<(&'g Constraint, bool)>,
+}
+
+/// A code to propose to a verifier, whose answer tells
+/// whether the verifier checks the constraint.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Proposal {
+    pub code: Code,
+    pub constraint: ConstraintID,
+    /// Whether the verifier accepts the code if it checks the constraint.
+    /// Every other constraint of the card still possible answers the
+    /// other way.
+    pub accepted: bool,
+}
+
+impl Proposal {
+    /// Whether the verifier checks the constraint,
+    /// given whether it accepted the code.
+    pub fn verified(&self, accepted: bool) -> bool {
+        accepted == self.accepted
+    }
+}
+
+impl Game {
+    pub fn new(tree: DecisionTree) -> Self {
+        Game {
+            tree,
+            answers: vec![],
+        }
+    }
+
+    pub fn tree(&self) -> &DecisionTree {
+        &self.tree
+    }
+
+    pub fn answers(&self) -> &[Guess] {
+        &self.answers
+    }
+
+    pub fn position(&self) -> Position {
+        self.tree.position(&self.answers)
+    }
+
+    /// Record an answer, whether or not the tree recommended the guess.
+    pub fn record(&mut self, answer: Guess) {
+        self.answers.push(answer);
+    }
+
+    /// Answer the recommended guess, returning it,
+    /// or `None` if there is no guess left to make.
+    pub fn answer(&mut self, verified: bool) -> Option<Guess> {
+        let answer = Guess {
+            constraint: self.position().next_guess?,
+            verified,
+        };
+        self.record(answer);
+        Some(answer)
+    }
+
+    /// Take back the last answer.
+    pub fn undo(&mut self) -> Option<Guess> {
+        self.answers.pop()
+    }
+
+    /// A code to propose to the verifier of the recommended guess, so
+    /// that its answer tells whether the guessed constraint holds. Codes
+    /// still possible come first, as the proposal may then be the code.
+    ///
+    /// Returns `None` if there is no guess left to make, or if no code
+    /// of the domain sets the constraint apart from the other ones of
+    /// its card still possible.
+    pub fn proposal(&self, domain: Domain) -> Option<Proposal> {
+        let position = self.position();
+        let guess = position.next_guess?;
+        let constraint = self.tree.constraint(guess)?;
+        let verifier = self
+            .verifiers()
+            .into_iter()
+            .find(|v| v.card == guess.card)?;
+        let others = verifier
+            .constraints
+            .into_iter()
+            .filter(|&(other, possible)| possible && other.id != guess)
+            .map(|(other, _)| other)
+            .collect_vec();
+        position
+            .candidates
+            .into_iter()
+            .chain(domain.codes())
+            .find_map(|code| {
+                let accepted = constraint.verify(&code);
+                others
+                    .iter()
+                    .all(|other| other.verify(&code) != accepted)
+                    .then_some(Proposal {
+                        code,
+                        constraint: guess,
+                        accepted,
+                    })
+            })
+    }
+
+    /// The constraints each verifier may be, by card, marking
+    /// those that some solution left by the answers relies on.
+    pub fn verifiers(&self) -> Vec<VerifierState<'_>> {
+        let remaining = shrink(&self.tree.solutions.iter().collect_vec(), &self.answers);
+        self.tree
+            .solutions
+            .iter()
+            .flat_map(|s| s.sorted_constraints())
+            .unique_by(|c| c.id)
+            .sorted_by_key(|c| c.id)
+            .chunk_by(|c| c.id.card)
+            .into_iter()
+            .map(|(card, constraints)| VerifierState {
+                card,
+                constraints: constraints
+                    .map(|c| (c, remaining.iter().any(|s| s.contains(c.id))))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn game_tracks_answers() {
+    let mut game = Game::new(crate::guess_sequence::example_tree());
+    assert_eq!(
+        game.verifiers().iter().map(|v| v.card).collect_vec(),
+        [3, 10, 14, 17]
+    );
+    assert!(
+        game.verifiers()
+            .iter()
+            .all(|v| v.constraints.iter().all(|c| c.1))
+    );
+
+    let first = game.answer(true).unwrap();
+    assert_eq!(game.answers(), [first]);
+    let card = game
+        .verifiers()
+        .into_iter()
+        .find(|v| v.card == first.constraint.card)
+        .unwrap();
+    assert!(
+        card.constraints
+            .iter()
+            .all(|(c, possible)| *possible == (c.id == first.constraint))
+    );
+
+    while game.answer(false).is_some() {}
+    assert!(game.position().solution().is_some());
+    assert_eq!(game.answer(true), None);
+    let last = game.answers().last().copied();
+    assert_eq!(game.undo(), last);
+    assert!(game.position().next_guess.is_some());
+}
+
+#[test]
+fn proposal_sets_the_guess_apart() {
+    let mut game = Game::new(crate::guess_sequence::example_tree());
+    let domain = Domain::default();
+    while let Some(proposal) = game.proposal(domain) {
+        let guess = game.position().next_guess.unwrap();
+        assert_eq!(proposal.constraint, guess);
+        assert!(domain.contains(&proposal.code));
+        let verifier = game
+            .verifiers()
+            .into_iter()
+            .find(|v| v.card == guess.card)
+            .unwrap();
+        for (constraint, possible) in verifier.constraints {
+            if possible {
+                assert_eq!(
+                    constraint.verify(&proposal.code) == proposal.accepted,
+                    constraint.id == guess
+                );
+            }
+        }
+        assert!(proposal.verified(proposal.accepted));
+        game.answer(proposal.verified(false));
+    }
+    assert!(game.position().solution().is_some());
+}
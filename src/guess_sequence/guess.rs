@@ -1,24 +1,79 @@
-use crate::scenario::constraint::Constraint;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-pub struct Guess<'a> {
-    pub constraint: &'a Constraint,
+use crate::{
+    render::{Render, Wording},
+    scenario::constraint::ConstraintID,
+    solver::Solution,
+};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct Guess {
+    pub constraint: ConstraintID,
     pub verified: bool,
 }
 
-impl<'a> std::fmt::Display for Guess<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Guess {
+    /// Whether this answer is consistent with the provided solution.
+    pub fn permits(&self, solution: &Solution) -> bool {
+        solution.contains(self.constraint) == self.verified
+    }
+}
+
+impl Render for Guess {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
         write!(
             f,
             "{}{}",
-            if self.verified { "" } else { "!" },
+            if self.verified {
+                ""
+            } else {
+                wording.negation()
+            },
             self.constraint
         )
     }
 }
 
-impl<'a> std::fmt::Debug for Guess<'a> {
+impl std::fmt::Display for Guess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, Wording::default())
+    }
+}
+
+/// Parses answers written as in the tree, e.g. `3.0` or `!3.0`.
+impl std::str::FromStr for Guess {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (verified, id) = match s.strip_prefix('!') {
+            Some(id) => (false, id),
+            None => (true, s),
+        };
+        let id = id.trim_start_matches('(').trim_end_matches(')');
+        let Some((card, idx)) = id.split_once('.') else {
+            return Err("Answers must look like 3.0 or !3.0");
+        };
+        Ok(Guess {
+            constraint: ConstraintID {
+                card: card.parse().map_err(|_| "Invalid card number")?,
+                idx: idx.parse().map_err(|_| "Invalid constraint number")?,
+            },
+            verified,
+        })
+    }
+}
+
+impl std::fmt::Debug for Guess {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self, f)
     }
 }
+
+#[test]
+fn guess_from_str() {
+    for answer in ["(3.0)", "!(14.2)"] {
+        assert_eq!(answer.parse::<Guess>().unwrap().to_string(), answer);
+    }
+    assert_eq!("!3.1".parse::<Guess>().unwrap().to_string(), "!(3.1)");
+    assert!("3".parse::<Guess>().is_err());
+}
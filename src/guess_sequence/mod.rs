@@ -1,8 +1,22 @@
 mod decision;
 mod decision_tree;
+mod game;
 mod guess;
+mod node;
+mod position;
 pub mod solver;
+pub mod strategy;
 
-pub(super) use decision::{Branch, Decision};
-pub(super) use decision_tree::DecisionTree;
-pub(super) use guess::Guess;
+pub(super) use decision::{Branch, Candidates, Decision, forced_code};
+pub use decision_tree::{CompactTree, DecisionTree, IncompleteTree, TreeStats};
+pub use game::{Game, Proposal, VerifierState};
+pub use guess::Guess;
+pub use node::{Outcome, TreeNode};
+pub use position::Position;
+pub use strategy::Strategy;
+
+/// The decision tree of the README example, `3 10 14 17`, shared by tests.
+#[cfg(test)]
+pub(crate) fn example_tree() -> DecisionTree {
+    solver::guess_sequence(&crate::solver::example_solutions()).unwrap()
+}
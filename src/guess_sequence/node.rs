@@ -0,0 +1,104 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    guess_sequence::{Decision, DecisionTree},
+    scenario::{code::Code, constraint::ConstraintID},
+};
+
+/// What is known at some point of a decision tree.
+///
+/// This is a nested, owned form of [`DecisionTree`]
+/// meant to be stored, sent across threads or serialized.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The code is identified.
+    Solution(Code),
+    /// No guess can tell these codes apart.
+    Unresolved(Vec<Code>),
+    /// Another guess is needed.
+    Guess(Box<TreeNode>),
+}
+
+/// A guess along with what follows from either answer to it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub constraint: ConstraintID,
+    pub name: String,
+    /// `None` if no remaining solution permits the answer.
+    pub verified: Option<Outcome>,
+    pub unverified: Option<Outcome>,
+}
+
+impl DecisionTree {
+    /// Convert to an owned, nested representation.
+    pub fn to_outcome(&self) -> Outcome {
+        if let Some(code) = self.solution {
+            return Outcome::Solution(code);
+        }
+        self.outcome_for(&self.root(), None)
+    }
+
+    fn outcome_for(&self, candidates: &[usize], solution: Option<Code>) -> Outcome {
+        if let Some(code) = solution {
+            return Outcome::Solution(code);
+        }
+        let Some(branch) = self.decisions.get(candidates) else {
+            return Outcome::Unresolved(
+                candidates
+                    .iter()
+                    .map(|&idx| self.solutions[idx].code)
+                    .unique()
+                    .collect(),
+            );
+        };
+        let Some(constraint) = branch
+            .decisions()
+            .next()
+            .map(|d| d.current_guess.constraint)
+        else {
+            return Outcome::Unresolved(vec![]);
+        };
+        let outcome = |decision: &Option<Decision>| -> Option<Outcome> {
+            decision
+                .as_ref()
+                .map(|d| self.outcome_for(&d.remaining, d.solution))
+        };
+        Outcome::Guess(Box::new(TreeNode {
+            constraint,
+            name: self
+                .constraint(constraint)
+                .map(|c| c.name.to_string())
+                .unwrap_or_default(),
+            verified: outcome(&branch.verified),
+            unverified: outcome(&branch.unverified),
+        }))
+    }
+}
+
+#[test]
+fn owned_tree_round_trip() {
+    fn assert_send_sync<T: Send + Sync + 'static>(_: &T) {}
+
+    let tree = crate::guess_sequence::example_tree();
+    assert_send_sync(&tree);
+    let outcome = tree.to_outcome();
+    assert_send_sync(&outcome);
+
+    let json = serde_json::to_string(&outcome).unwrap();
+    assert_eq!(serde_json::from_str::<Outcome>(&json).unwrap(), outcome);
+
+    fn count_solutions(outcome: &Outcome) -> usize {
+        match outcome {
+            Outcome::Solution(_) => 1,
+            Outcome::Unresolved(_) => 0,
+            Outcome::Guess(node) => [&node.verified, &node.unverified]
+                .into_iter()
+                .flatten()
+                .map(count_solutions)
+                .sum(),
+        }
+    }
+    assert_eq!(count_solutions(&outcome), tree.leaves().len());
+}
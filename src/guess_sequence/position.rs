@@ -0,0 +1,207 @@
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    guess_sequence::{DecisionTree, Guess, forced_code, solver::next_query},
+    scenario::{code::Code, constraint::ConstraintID},
+    solver::Solution,
+};
+
+/// Where a game stands after some guesses have been answered.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Position {
+    /// The recommended next guess, or `None` if the code
+    /// is known or no guess can narrow the candidates down.
+    pub next_guess: Option<ConstraintID>,
+    /// The codes still possible, in solution order.
+    pub candidates: Vec<Code>,
+}
+
+impl Position {
+    /// The code, if the answers so far identify it.
+    pub fn solution(&self) -> Option<Code> {
+        self.candidates.iter().copied().exactly_one().ok()
+    }
+}
+
+impl DecisionTree {
+    /// Determine the next guess to make and the codes still possible
+    /// after the provided answers.
+    ///
+    /// The answers may be given in any order, and may include guesses
+    /// the tree would not have asked: the position is re-derived from
+    /// the solutions that permit every answer, as [`shrink`] does.
+    ///
+    /// [`shrink`]: crate::guess_sequence::solver::shrink
+    pub fn position(&self, answers: &[Guess]) -> Position {
+        let remaining = self
+            .solutions
+            .iter()
+            .positions(|solution| answers.iter().all(|answer| answer.permits(solution)))
+            .collect_vec();
+        let remaining_solutions = remaining
+            .iter()
+            .map(|&idx| &self.solutions[idx])
+            .collect_vec();
+        let candidates = remaining_solutions
+            .iter()
+            .map(|s| s.code)
+            .unique()
+            .collect_vec();
+        if remaining.is_empty() || forced_code(&remaining_solutions).is_some() {
+            return Position {
+                next_guess: None,
+                candidates,
+            };
+        }
+        let next_guess = match self.decisions.get(&remaining) {
+            Some(branch) => branch
+                .decisions()
+                .next()
+                .map(|d| d.current_guess.constraint),
+            None if self.unresolved.contains(&remaining) => None,
+            // The answers left the tree's paths, so let the tree's
+            // strategy pick a guess for the remaining solutions.
+            None => {
+                let constraints = remaining_solutions
+                    .iter()
+                    .flat_map(|s| s.constraints.iter())
+                    .unique_by(|c| c.id)
+                    .sorted_by_key(|c| c.id)
+                    .collect_vec();
+                next_query(&self.strategy, &constraints, &remaining_solutions).map(|c| c.id)
+            }
+        };
+        Position {
+            next_guess,
+            candidates,
+        }
+    }
+}
+
+impl DecisionTree {
+    /// Play a game against the provided secret, following the tree,
+    /// and return the answers received until the code is known or
+    /// no guess can narrow the candidates down.
+    pub fn play(&self, secret: &Solution) -> Vec<Guess> {
+        let mut answers = vec![];
+        while let Some(constraint) = self.position(&answers).next_guess {
+            answers.push(Guess {
+                constraint,
+                verified: secret.contains(constraint),
+            });
+        }
+        answers
+    }
+}
+
+#[test]
+fn position_ignores_answer_order() {
+    let tree = crate::guess_sequence::example_tree();
+
+    let start = tree.position(&[]);
+    assert_eq!(
+        start.candidates.len(),
+        tree.solutions.iter().map(|s| s.code).unique().count()
+    );
+    let mut answers = vec![];
+    let mut positions = vec![];
+    let mut position = start;
+    while let Some(constraint) = position.next_guess {
+        answers.push(Guess {
+            constraint,
+            verified: false,
+        });
+        positions.push(position);
+        position = tree.position(&answers);
+    }
+    assert!(answers.len() > 1);
+    let code = position.solution().unwrap();
+
+    for (len, expected) in positions.iter().enumerate() {
+        let mut prefix = answers[..len].to_vec();
+        prefix.reverse();
+        assert_eq!(&tree.position(&prefix), expected);
+        assert!(expected.candidates.contains(&code));
+    }
+    answers.reverse();
+    assert_eq!(tree.position(&answers).solution(), Some(code));
+}
+
+#[test]
+fn position_off_tree() {
+    let tree = crate::guess_sequence::example_tree();
+    let first = tree.position(&[]).next_guess.unwrap();
+    let other = ConstraintID { card: 17, idx: 2 };
+    assert_ne!(first, other);
+
+    let answer = |constraint, verified| Guess {
+        constraint,
+        verified,
+    };
+    let position = tree.position(&[answer(other, true)]);
+    assert!(position.next_guess.is_some());
+    assert!(position.candidates.len() < tree.position(&[]).candidates.len());
+
+    let contradiction = tree.position(&[answer(other, true), answer(other, false)]);
+    assert_eq!(contradiction.next_guess, None);
+    assert!(contradiction.candidates.is_empty());
+}
+
+#[test]
+fn play_finds_every_code() {
+    let tree = crate::guess_sequence::example_tree();
+    for secret in &tree.solutions {
+        let answers = tree.play(secret);
+        assert!(answers.len() <= tree.worst_case_path_length());
+        assert_eq!(tree.position(&answers).solution(), Some(secret.code));
+    }
+}
+
+#[test]
+fn position_off_tree_uses_the_tree_strategy() {
+    use crate::{
+        guess_sequence::{
+            solver::{guess_sequence, guess_sequence_with},
+            strategy::Strategy,
+        },
+        scenario::constraint::Constraint,
+        solver::example_solutions,
+    };
+    /// Queries the constraint with the highest id.
+    struct Last;
+    impl Strategy for Last {
+        fn next_query<'a>(
+            &self,
+            queries: &[&'a Constraint],
+            _: &[&Solution],
+        ) -> Option<&'a Constraint> {
+            queries.last().copied()
+        }
+    }
+    let solutions = example_solutions();
+    let tree = guess_sequence_with(&solutions, Last).unwrap();
+    let answers = [Guess {
+        constraint: ConstraintID { card: 3, idx: 0 },
+        verified: false,
+    }];
+    assert_ne!(tree.position(&[]).next_guess, Some(answers[0].constraint));
+
+    let remaining = solutions
+        .iter()
+        .filter(|s| answers[0].permits(s))
+        .collect_vec();
+    let last = remaining
+        .iter()
+        .flat_map(|s| s.constraints.iter().map(|c| c.id))
+        .filter(|&id| !remaining.iter().all(|s| s.contains(id)))
+        .max();
+    assert_eq!(tree.position(&answers).next_guess, last);
+    assert_ne!(
+        guess_sequence(&solutions)
+            .unwrap()
+            .position(&answers)
+            .next_guess,
+        last
+    );
+}
@@ -1,59 +1,65 @@
 use itertools::Itertools;
-use log::{Level, debug, error, log_enabled};
+use log::{Level, debug, log_enabled};
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    guess_sequence::{Branch, Decision, DecisionTree, Guess},
+    guess_sequence::{
+        Branch, Candidates, DecisionTree, Guess, IncompleteTree, forced_code,
+        strategy::{Balance, Strategy},
+    },
     scenario::constraint::Constraint,
     solver::Solution,
 };
 
-/// Determine the best possible guess sequences that converge to a solution.
-pub fn guess_sequence(solutions: &Vec<Solution>) -> Option<DecisionTree> {
-    let relevant_constraints: HashSet<&Constraint> = solutions.iter().fold(
-        HashSet::<&Constraint>::new(),
-        |mut constraints, solution| {
-            constraints.extend(solution.constraints.iter());
-            constraints
-        },
-    );
-
-    let mut decisions: HashMap<Decision, Branch> = HashMap::new();
-    let Some(first_best_guess) = best_guess(
-        &relevant_constraints,
-        &solutions.iter().map(|s| s).collect_vec(),
-    ) else {
-        return None
-    };
+/// Determine the best possible guess sequences that converge to a solution,
+/// querying the constraints the [`Balance`] strategy picks.
+///
+/// Solutions with distinct codes always differ in some constraint, so
+/// the built-in strategies always complete the tree.
+pub fn guess_sequence(solutions: &[Solution]) -> Result<DecisionTree, IncompleteTree> {
+    guess_sequence_with(solutions, Balance)
+}
 
-    let available_solutions: Vec<&Solution> = solutions.iter().map(|s| s).collect_vec();
+/// Determine guess sequences that converge to a solution,
+/// querying the constraints the provided strategy picks.
+///
+/// The tree keeps the strategy to pick guesses for answers off its paths.
+/// If the strategy declines to pick a query for some candidates, the
+/// partially built tree is returned as an [`IncompleteTree`] listing
+/// them with the guesses leading there.
+pub fn guess_sequence_with<S: Strategy + Send + Sync + 'static>(
+    solutions: &[Solution],
+    strategy: S,
+) -> Result<DecisionTree, IncompleteTree> {
+    let relevant_constraints = solutions
+        .iter()
+        .flat_map(|solution| solution.constraints.iter())
+        .unique_by(|c| c.id)
+        .sorted_by_key(|c| c.id)
+        .collect_vec();
 
-    let roots = vec![
-        Decision::prepare(
-            vec![],
-            Guess {
-                constraint: first_best_guess,
-                verified: true,
-            },
-            &available_solutions,
-        ),
-        Decision::prepare(
-            vec![],
-            Guess {
-                constraint: first_best_guess,
-                verified: false,
-            },
-            &available_solutions,
-        ),
-    ];
-    let mut unbranched_decisions: Vec<Decision> = roots.clone();
+    let available_solutions: Vec<&Solution> = solutions.iter().collect_vec();
+    let mut tree = DecisionTree {
+        solutions: solutions.to_vec(),
+        decisions: HashMap::new(),
+        unresolved: HashSet::new(),
+        solution: forced_code(&available_solutions),
+        strategy: Box::new(strategy),
+    };
+    if tree.solution.is_some() {
+        return tree.into_result();
+    }
 
-    while let Some(decision) = unbranched_decisions.pop() {
-        if decision.solution != None {
+    // Each candidate set is branched at most once.
+    let mut unbranched: Vec<Candidates> = vec![tree.root()];
+    while let Some(candidates) = unbranched.pop() {
+        if tree.decisions.contains_key(&candidates) || tree.unresolved.contains(&candidates) {
             continue;
         }
-        let remaining_solutions = &shrink(&available_solutions, &decision.guesses);
-        debug!("For guess sequence: {:?}", decision.guesses);
+        let remaining_solutions = candidates
+            .iter()
+            .map(|&idx| available_solutions[idx])
+            .collect_vec();
         if log_enabled!(Level::Debug) {
             debug!(
                 "Remaining possible solutions: {}",
@@ -63,87 +69,52 @@ pub fn guess_sequence(solutions: &Vec<Solution>) -> Option<DecisionTree> {
                 debug!("\t{}", solution)
             }
         }
-        let remaining_constraints: HashSet<&Constraint> = relevant_constraints
-            .iter()
-            .filter(|&&c| c != decision.current_guess.constraint)
-            .map(|c| *c)
-            .collect();
-        if let Some(best_guess) = best_guess(&remaining_constraints, remaining_solutions) {
-            let verified_decision = Decision::prepare(
-                decision.guesses.iter().copied().collect(),
-                Guess {
-                    constraint: best_guess,
-                    verified: true,
-                },
-                &available_solutions,
+        if let Some(best_guess) =
+            next_query(&tree.strategy, &relevant_constraints, &remaining_solutions)
+        {
+            let branch = Branch::prepare(&candidates, best_guess.id, &available_solutions);
+            unbranched.extend(
+                branch
+                    .decisions()
+                    .filter(|d| d.solution.is_none())
+                    .map(|d| d.remaining.clone()),
             );
-
-            let unverified_decision = Decision::prepare(
-                decision.guesses.iter().copied().collect(),
-                Guess {
-                    constraint: best_guess,
-                    verified: false,
-                },
-                &available_solutions,
-            );
-            decisions.insert(
-                decision,
-                Branch {
-                    verified: Some(verified_decision.clone()),
-                    unverified: Some(unverified_decision.clone()),
-                },
-            );
-            unbranched_decisions.push(verified_decision);
-            unbranched_decisions.push(unverified_decision);
+            tree.decisions.insert(candidates, branch);
         } else {
-            error!("Beep")
+            debug!("No guess distinguishes the remaining solutions");
+            tree.unresolved.insert(candidates);
         }
     }
 
-    return Some(DecisionTree {
-        roots: roots,
-        decisions: decisions,
-    });
-}
-
-/// The difference between the number of solutions with
-/// the provided constraint verified vs the number of
-/// solutions with the provided constraint unverified
-fn tf_difference(constraint: &Constraint, solutions: &Vec<&Solution>) -> u32 {
-    let tf_set = solutions
-        .iter()
-        .fold(HashMap::<bool, u32>::new(), |mut tf_set, solution| {
-            *tf_set
-                .entry(solution.constraints.contains(constraint))
-                .or_insert(0) += 1;
-            tf_set
-        });
-
-    u32::abs_diff(
-        *tf_set.get(&true).unwrap_or(&0),
-        *tf_set.get(&false).unwrap_or(&0),
-    )
+    tree.into_result()
 }
 
-/// Determine the ideal constraint
-/// to guess using an
-/// implementation-specific heuristic
-fn best_guess<'a>(
-    available_constraints: &HashSet<&'a Constraint>,
-    possible_solutions: &Vec<&Solution>,
+/// Ask the strategy which of the constraints to guess next.
+///
+/// Only constraints that some, but not all, of the possible
+/// solutions contain are offered, since any other guess
+/// has a known answer.
+pub(super) fn next_query<'a, S: Strategy + ?Sized>(
+    strategy: &S,
+    available_constraints: &[&'a Constraint],
+    possible_solutions: &[&Solution],
 ) -> Option<&'a Constraint> {
-    let best_guess = available_constraints
+    let queries = available_constraints
         .iter()
-        .min_by(|&&c1, &&c2| {
-            tf_difference(c1, possible_solutions).cmp(&tf_difference(c2, possible_solutions))
+        .filter(|c| {
+            let verified = possible_solutions
+                .iter()
+                .filter(|s| s.constraints.contains(**c))
+                .count();
+            verified != 0 && verified != possible_solutions.len()
         })
-        .map(|g| *g);
+        .copied()
+        .collect_vec();
+    let best_guess = strategy
+        .next_query(&queries, possible_solutions)
+        .filter(|guess| queries.iter().any(|q| q.id == guess.id));
     if let Some(best_guess) = best_guess {
-        debug!(
-            "Next best guess {} ({})",
-            best_guess,
-            tf_difference(best_guess, possible_solutions)
-        );
+        debug!("Next best guess {}", best_guess);
     }
     best_guess
 }
@@ -152,21 +123,13 @@ fn best_guess<'a>(
 /// to include only solutions
 /// that contain only solutions possible
 /// with the provided guess sequence
-pub(super) fn shrink<'a>(
-    available_solutions: &Vec<&'a Solution>,
-    guess_sequence: &Vec<Guess<'a>>,
+pub fn shrink<'a>(
+    available_solutions: &[&'a Solution],
+    guess_sequence: &[Guess],
 ) -> Vec<&'a Solution> {
     available_solutions
         .iter()
-        .filter(|&&solution| {
-            guess_sequence.iter().all(|&guess| {
-                if guess.verified {
-                    solution.constraints.contains(guess.constraint)
-                } else {
-                    !solution.constraints.contains(guess.constraint)
-                }
-            })
-        })
-        .map(|s| *s)
+        .filter(|&&solution| guess_sequence.iter().all(|guess| guess.permits(solution)))
+        .copied()
         .collect_vec()
 }
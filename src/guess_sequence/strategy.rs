@@ -0,0 +1,325 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use itertools::Itertools;
+use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde::{Deserialize, de::Error};
+
+use crate::{scenario::constraint::Constraint, solver::Solution};
+
+/// Picks the constraint to query next while building a decision tree.
+///
+/// `queries` holds, ordered by id, the constraints that some but not all
+/// of the `remaining` solutions contain, so every query narrows them down.
+/// Returning `None`, or a constraint that is not among `queries`, leaves
+/// the remaining solutions unresolved.
+pub trait Strategy {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        remaining: &[&Solution],
+    ) -> Option<&'a Constraint>;
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        remaining: &[&Solution],
+    ) -> Option<&'a Constraint> {
+        (**self).next_query(queries, remaining)
+    }
+}
+
+/// Query the constraint splitting the solutions most evenly
+/// between verified and rejected, the lowest id on a tie.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Balance;
+
+impl Strategy for Balance {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        remaining: &[&Solution],
+    ) -> Option<&'a Constraint> {
+        queries
+            .iter()
+            .min_by_key(|&&c| tf_difference(c, remaining))
+            .copied()
+    }
+}
+
+/// Query the constraint expected to tell the most about the code.
+///
+/// Unlike [`Balance`], it weighs solutions by the code they lead to, so
+/// splitting interpretations of the same code is worth nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Entropy;
+
+impl Strategy for Entropy {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        remaining: &[&Solution],
+    ) -> Option<&'a Constraint> {
+        let total = remaining.len() as f64;
+        queries
+            .iter()
+            .map(|&c| {
+                let (verified, rejected): (Vec<&Solution>, Vec<&Solution>) =
+                    remaining.iter().partition(|s| s.contains(c.id));
+                let left = [verified, rejected]
+                    .iter()
+                    .map(|part| part.len() as f64 / total * code_entropy(part))
+                    .sum::<f64>();
+                (c, left)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(c, _)| c)
+    }
+}
+
+/// The entropy, in bits, of the code of a solution drawn at random.
+fn code_entropy(solutions: &[&Solution]) -> f64 {
+    let total = solutions.len() as f64;
+    solutions
+        .iter()
+        .counts_by(|s| s.code)
+        .into_values()
+        .map(|count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Query the constraint minimising the number of queries
+/// needed in the worst case.
+///
+/// Up to [`Minimax::EXACT_LIMIT`] solutions, every tree is searched;
+/// above it, the constraint leaving the fewest codes in the worst case
+/// is queried instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Minimax;
+
+impl Minimax {
+    pub const EXACT_LIMIT: usize = 16;
+}
+
+impl Strategy for Minimax {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        remaining: &[&Solution],
+    ) -> Option<&'a Constraint> {
+        let worst_codes = |c: &Constraint| {
+            let (verified, rejected): (Vec<&Solution>, Vec<&Solution>) =
+                remaining.iter().partition(|s| s.contains(c.id));
+            [verified, rejected]
+                .iter()
+                .map(|part| part.iter().map(|s| s.code).unique().count())
+                .max()
+                .unwrap_or_default()
+        };
+        if remaining.len() > Self::EXACT_LIMIT {
+            return queries.iter().min_by_key(|&&c| worst_codes(c)).copied();
+        }
+        let mut search = Search::new(queries, remaining);
+        let all = (1u64 << remaining.len()) - 1;
+        queries
+            .iter()
+            .enumerate()
+            .min_by_key(|&(idx, &c)| {
+                let accepted = search.accepted[idx];
+                let depth = search
+                    .depth(all & accepted)
+                    .max(search.depth(all & !accepted));
+                (depth, worst_codes(c))
+            })
+            .map(|(_, &c)| c)
+    }
+}
+
+/// An exhaustive search for the shallowest decision tree,
+/// with candidate sets as bit masks over the solutions.
+struct Search<'s> {
+    remaining: &'s [&'s Solution],
+    /// For each query, the solutions it accepts.
+    accepted: Vec<u64>,
+    depths: HashMap<u64, u32>,
+}
+
+impl<'s> Search<'s> {
+    fn new(queries: &[&Constraint], remaining: &'s [&'s Solution]) -> Self {
+        let accepted = queries
+            .iter()
+            .map(|c| {
+                remaining
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| s.contains(c.id))
+                    .fold(0, |mask, (idx, _)| mask | 1 << idx)
+            })
+            .collect();
+        Search {
+            remaining,
+            accepted,
+            depths: HashMap::new(),
+        }
+    }
+
+    /// The number of queries needed in the worst case to find the code
+    /// among the candidates, or 0 if no query tells them apart.
+    fn depth(&mut self, candidates: u64) -> u32 {
+        if let Some(&depth) = self.depths.get(&candidates) {
+            return depth;
+        }
+        let codes: HashSet<_> = (0..self.remaining.len())
+            .filter(|idx| candidates & 1 << idx != 0)
+            .map(|idx| self.remaining[idx].code)
+            .collect();
+        let depth = match codes.len() {
+            0 | 1 => 0,
+            _ => (0..self.accepted.len())
+                .filter_map(|idx| {
+                    let accepted = self.accepted[idx];
+                    (candidates & accepted != 0 && candidates & !accepted != 0).then(|| {
+                        1 + self
+                            .depth(candidates & accepted)
+                            .max(self.depth(candidates & !accepted))
+                    })
+                })
+                .min()
+                .unwrap_or(0),
+        };
+        self.depths.insert(candidates, depth);
+        depth
+    }
+}
+
+/// Query a constraint picked at random, as a baseline
+/// to compare other strategies against.
+#[derive(Debug)]
+pub struct Random {
+    rng: Mutex<StdRng>,
+}
+
+impl Random {
+    /// A strategy repeating the same picks for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        Random {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random {
+            rng: Mutex::new(rand::make_rng()),
+        }
+    }
+}
+
+impl Strategy for Random {
+    fn next_query<'a>(
+        &self,
+        queries: &[&'a Constraint],
+        _remaining: &[&Solution],
+    ) -> Option<&'a Constraint> {
+        let mut rng = self.rng.lock().unwrap_or_else(|err| err.into_inner());
+        queries.choose(&mut *rng).copied()
+    }
+}
+
+/// The built-in strategies, by the name they are chosen with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StrategyName {
+    /// Guess the constraint that splits the candidates most evenly
+    #[default]
+    Balance,
+    /// Guess the constraint expected to tell the most about the code
+    Entropy,
+    /// Guess the constraint needing the fewest guesses in the worst case
+    Minimax,
+    /// Guess a constraint at random
+    Random,
+}
+
+impl StrategyName {
+    /// The strategy picking guesses, seeding random picks if a seed is given.
+    pub fn picker(self, seed: Option<u64>) -> Box<dyn Strategy + Send + Sync> {
+        match self {
+            StrategyName::Balance => Box::new(Balance),
+            StrategyName::Entropy => Box::new(Entropy),
+            StrategyName::Minimax => Box::new(Minimax),
+            StrategyName::Random => match seed {
+                Some(seed) => Box::new(Random::seeded(seed)),
+                None => Box::new(Random::default()),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for StrategyName {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "balance" => Ok(StrategyName::Balance),
+            "entropy" => Ok(StrategyName::Entropy),
+            "minimax" => Ok(StrategyName::Minimax),
+            "random" => Ok(StrategyName::Random),
+            _ => Err("Strategy must be one of balance, entropy, minimax or random"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StrategyName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// The difference between the number of solutions with
+/// the provided constraint verified vs the number of
+/// solutions with the provided constraint unverified
+fn tf_difference(constraint: &Constraint, solutions: &[&Solution]) -> usize {
+    let verified = solutions
+        .iter()
+        .filter(|s| s.contains(constraint.id))
+        .count();
+    verified.abs_diff(solutions.len() - verified)
+}
+
+#[test]
+fn strategies_resolve_every_code() {
+    use crate::{guess_sequence::solver::guess_sequence_with, solver::example_solutions};
+    let solutions = example_solutions();
+    let even = [(3, 1), (4, 13), (5, 2)];
+    let strategies: [(StrategyName, &[(usize, usize)]); 4] = [
+        (StrategyName::Balance, &even),
+        (StrategyName::Entropy, &even),
+        (StrategyName::Minimax, &even),
+        (
+            StrategyName::Random,
+            &[(2, 1), (3, 1), (4, 7), (5, 5), (6, 2)],
+        ),
+    ];
+    for (strategy, leaf_depths) in strategies {
+        let tree = guess_sequence_with(&solutions, strategy.picker(Some(3))).unwrap();
+        let stats = tree.stats();
+        assert_eq!(stats.leaves, solutions.len());
+        assert_eq!(stats.dead_ends, 0);
+        assert_eq!(stats.leaf_depths, leaf_depths.iter().copied().collect());
+        assert_eq!(stats.depth, leaf_depths.last().unwrap().0);
+        for solution in &solutions {
+            let answers = tree.play(solution);
+            assert!(answers.len() <= stats.worst_case_path_length);
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod catalogue;
+pub mod config;
 pub mod guess_sequence;
+pub mod puzzle;
+pub mod render;
 pub mod scenario;
+#[cfg(feature = "serve")]
+pub mod server;
 pub mod solver;
+#[cfg(feature = "tui")]
+pub mod tui;
@@ -1,115 +1,945 @@
-use clap::Parser;
+use std::{
+    ffi::OsStr,
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+use clap::{
+    Arg, CommandFactory, Parser, Subcommand,
+    builder::{PossibleValue, StringValueParser, TypedValueParser},
+};
+use clap_complete::Shell;
 use itertools::Itertools;
-use std::{collections::HashSet, str::FromStr};
+use rand::{SeedableRng, rngs::StdRng};
 use turing_solve::{
-    guess_sequence::solver::guess_sequence,
-    solver::{Constraint, constraints_for_card, turing_solve},
+    catalogue::Catalogue,
+    config::{Config, OutputFormat},
+    guess_sequence::{
+        DecisionTree, Game, Guess, IncompleteTree,
+        solver::{guess_sequence_with, shrink},
+        strategy::StrategyName,
+    },
+    puzzle::{self, Puzzle, Rating, Simulation},
+    render::{CodeGrid, Locale, Style, Wording},
+    solver::{
+        Constraint, Domain, GameMode, ScenarioBuilder, ScenarioError, Solution, VerifierSpec,
+        constraints_for_card, group_by_code, verifier_limits,
+    },
 };
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 /// A command line utility for creating a decision
 /// tree for identifying the solution to a game of Turing Machine.
+///
+/// Verifiers given without a subcommand are solved as by `solve`.
 struct Args {
-    #[arg(help = "Set of constraints to apply (max 6)")]
-    constraints: Vec<CardOrConstraintArg>,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    solve: SolveArgs,
+    #[arg(
+        long,
+        global = true,
+        env = "TURING_SOLVE_STYLE",
+        help = "How to write code colours: emoji, ascii or words [default: emoji]"
+    )]
+    style: Option<Style>,
+    #[arg(
+        long,
+        global = true,
+        env = "TURING_SOLVE_LOCALE",
+        help = "Language of the constraint names: en, fr or de [default: en]"
+    )]
+    locale: Option<Locale>,
+    #[arg(
+        long,
+        global = true,
+        env = "TURING_SOLVE_CONFIG",
+        help = "Configuration file [default: ~/.config/turing-solve/config.toml]"
+    )]
+    config: Option<PathBuf>,
 }
 
-#[derive(Clone)]
-pub enum CardOrConstraintArg {
-    Card(u8),
-    CardConstraint {
-        inverted: bool,
-        card_num: u8,
-        id: u8,
+/// The verifiers of a game and the rules it is played with.
+#[derive(clap::Args)]
+struct ScenarioArgs {
+    #[arg(
+        value_parser = CatalogueValues::new(str::parse::<VerifierSpec>, true),
+        hide_possible_values = true,
+        help = "Verifiers to apply: 40 for a whole card, 40.3 or 40.0,3,6 to pin, ^40.3 to exclude"
+    )]
+    constraints: Vec<VerifierSpec>,
+    #[command(flatten)]
+    rules: RulesArgs,
+}
+
+#[derive(clap::Args)]
+struct RulesArgs {
+    #[arg(
+        long,
+        help = "Game mode, which narrows down the 1 to 6 verifiers a game has: classic, extreme or nightmare"
+    )]
+    mode: Option<GameMode>,
+    #[command(flatten)]
+    codes: DomainArgs,
+}
+
+#[derive(clap::Args)]
+struct DomainArgs {
+    #[arg(long, default_value_t = 3, help = "Number of digits in a code")]
+    digits: u8,
+    #[arg(long, default_value_t = 5, help = "Largest value of a code digit")]
+    max_value: u8,
+}
+
+#[derive(clap::Args)]
+struct TreeArgs {
+    #[arg(long, help = "Print statistics about the decision tree")]
+    stats: bool,
+    #[arg(long, help = "Print repeated subtrees of the decision tree only once")]
+    compact: bool,
+    #[arg(long, help = "Output format: text or json [default: text]")]
+    format: Option<OutputFormat>,
+    #[command(flatten)]
+    strategy: StrategyArgs,
+}
+
+#[derive(clap::Args)]
+struct StrategyArgs {
+    #[arg(
+        long,
+        help = "How to choose guesses in the decision tree: balance, entropy, minimax or random [default: balance]"
+    )]
+    strategy: Option<StrategyName>,
+}
+
+#[derive(clap::Args)]
+struct SolveArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+    #[command(flatten)]
+    tree: TreeArgs,
+    #[arg(
+        long,
+        help = "List each possible code once with its verifier interpretations"
+    )]
+    group: bool,
+    #[arg(
+        long,
+        help = "Draw the codes still possible as grids, one per blue value"
+    )]
+    grid: bool,
+    #[arg(
+        long = "answer",
+        value_name = "ANSWER",
+        help = "Answer already given to a guess, e.g. 3.0 or !3.0; limits the grid"
+    )]
+    answers: Vec<Guess>,
+}
+
+/// How to deal random puzzles.
+#[derive(clap::Args)]
+struct DealArgs {
+    #[command(flatten)]
+    rules: RulesArgs,
+    #[arg(long, default_value_t = 4, help = "Number of verifier cards to deal")]
+    verifiers: usize,
+    #[arg(long, help = "Seed of the random deals, to repeat them")]
+    seed: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the solutions of a scenario and the decision tree telling them apart
+    Solve(SolveArgs),
+    /// Print only the decision tree of a scenario
+    Tree {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+        #[command(flatten)]
+        tree: TreeArgs,
+    },
+    /// Find the code interactively, answering the guesses the decision tree asks
+    Play {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+        #[command(flatten)]
+        strategy: StrategyArgs,
+        #[arg(
+            long = "answer",
+            value_name = "ANSWER",
+            help = "Answer already given to a guess, e.g. 3.0 or !3.0"
+        )]
+        answers: Vec<Guess>,
     },
+    /// Play full screen, with the verifiers, the codes left and the answers in view
+    #[cfg(feature = "tui")]
+    Tui {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+        #[command(flatten)]
+        strategy: StrategyArgs,
+        #[arg(
+            long = "answer",
+            value_name = "ANSWER",
+            help = "Answer already given to a guess, e.g. 3.0 or !3.0"
+        )]
+        answers: Vec<Guess>,
+    },
+    /// List every card and its constraints
+    Cards,
+    /// Show the constraints of a card and the codes each accepts
+    Card {
+        #[arg(
+            value_parser = CatalogueValues::new(parse_card_number, false),
+            hide_possible_values = true,
+            help = "Card number"
+        )]
+        number: u8,
+        #[arg(long, help = "Draw the codes each constraint accepts as grids")]
+        grid: bool,
+        #[command(flatten)]
+        codes: DomainArgs,
+    },
+    /// Check that every card constraint does what its name says
+    CheckCards {
+        #[arg(help = "Card file to check along with the printed cards")]
+        cards: Option<PathBuf>,
+    },
+    /// Deal random verifier cards that make a solvable puzzle
+    Generate {
+        #[command(flatten)]
+        deal: DealArgs,
+        #[arg(long, help = "Also print the secret solution")]
+        reveal: bool,
+    },
+    /// Rate how hard a scenario is to solve
+    Rate {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+        #[command(flatten)]
+        strategy: StrategyArgs,
+    },
+    /// Play random puzzles with the decision tree and report the queries needed
+    Simulate {
+        #[command(flatten)]
+        deal: DealArgs,
+        #[command(flatten)]
+        strategy: StrategyArgs,
+        #[arg(
+            long,
+            default_value_t = 1000,
+            conflicts_with = "puzzles",
+            help = "Number of random games to play"
+        )]
+        games: usize,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Play the puzzles of a file instead, one per line, e.g. 3 10 14 17 =122"
+        )]
+        puzzles: Option<PathBuf>,
+        #[arg(long, help = "Output format: text or json [default: text]")]
+        format: Option<OutputFormat>,
+    },
+    /// Serve a JSON API for solving scenarios and playing games over HTTP
+    #[cfg(feature = "serve")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:7878", help = "Address to listen on")]
+        address: String,
+        #[command(flatten)]
+        strategy: StrategyArgs,
+        #[arg(
+            long,
+            default_value_t = turing_solve::server::Api::MAX_SESSIONS,
+            help = "Number of games to keep, ending the one idle the longest to start another"
+        )]
+        max_sessions: usize,
+        #[arg(
+            long,
+            default_value_t = turing_solve::server::Api::IDLE.as_secs() / 60,
+            help = "Minutes after which a game without requests ends"
+        )]
+        idle_minutes: u64,
+        #[arg(
+            long = "allow-origin",
+            help = "Let web pages from this origin call the API, e.g. http://localhost:8080; repeat for more"
+        )]
+        allow_origins: Vec<String>,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[arg(help = "Shell to complete in")]
+        shell: Shell,
+    },
+    /// Print a man page in roff format
+    Man,
 }
 
-impl FromStr for CardOrConstraintArg {
-    type Err = &'static str;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Ok(num) = u8::from_str(s) {
-            Ok(CardOrConstraintArg::Card(num))
-        } else {
-            let Some(split): Option<(&str, &str)> = s.split(".").collect_tuple() else {
-                return Err("");
-            };
-            let inverted = split.0.starts_with('^');
-            let Ok(card_num) = u8::from_str(if inverted { &split.0[1..] } else { split.0 }) else {
-                return Err("");
-            };
-            let Ok(constraint_num) = u8::from_str(split.1) else {
-                return Err("");
-            };
-            Ok(CardOrConstraintArg::CardConstraint {
-                inverted: inverted,
-                card_num: card_num,
-                id: constraint_num,
+fn parse_card_number(s: &str) -> Result<u8, &'static str> {
+    s.parse().map_err(|_| "Invalid card number")
+}
+
+/// Parses card arguments, offering the cards of the catalogue
+/// and optionally their `card.idx` forms to shell completions.
+#[derive(Clone)]
+struct CatalogueValues<T> {
+    parse: fn(&str) -> Result<T, &'static str>,
+    constraints: bool,
+}
+
+impl<T> CatalogueValues<T> {
+    fn new(parse: fn(&str) -> Result<T, &'static str>, constraints: bool) -> Self {
+        CatalogueValues { parse, constraints }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> TypedValueParser for CatalogueValues<T> {
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<T, clap::Error> {
+        let parse = self.parse;
+        StringValueParser::new()
+            .try_map(move |value| parse(&value))
+            .parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let numbers = Catalogue::new().numbers().collect_vec();
+        let values = numbers.into_iter().flat_map(|number| {
+            let constraints = constraints_for_card(number).unwrap_or_default();
+            let card = PossibleValue::new(number.to_string())
+                .help(constraints.iter().map(card_text).join(" | "));
+            let constraints = constraints
+                .iter()
+                .filter(|_| self.constraints)
+                .map(|c| {
+                    PossibleValue::new(format!("{}.{}", c.id.card, c.id.idx)).help(card_text(c))
+                })
+                .collect_vec();
+            std::iter::once(card).chain(constraints)
+        });
+        Some(Box::new(values))
+    }
+}
+
+fn load_config(path: Option<&PathBuf>) -> Result<Config, &'static str> {
+    match path {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    }
+    .map_err(|err| {
+        eprintln!("{}", err);
+        "Invalid configuration"
+    })
+}
+
+/// Options resolved from the command line and the configuration file.
+struct Settings {
+    format: Option<OutputFormat>,
+    strategy: Option<StrategyName>,
+    wording: Wording,
+    catalogue: Catalogue,
+}
+
+impl Settings {
+    fn load(
+        path: Option<&PathBuf>,
+        style: Option<Style>,
+        locale: Option<Locale>,
+    ) -> Result<Self, &'static str> {
+        let config = load_config(path)?;
+        Ok(Settings {
+            wording: Wording::new(
+                style.or(config.style).unwrap_or_default(),
+                locale.or(config.locale).unwrap_or_default(),
+            ),
+            format: config.format,
+            strategy: config.strategy,
+            catalogue: config.catalogue().map_err(|err| {
+                eprintln!("{}", err);
+                "Invalid configuration"
+            })?,
+        })
+    }
+
+    fn format(&self, arg: Option<OutputFormat>) -> OutputFormat {
+        arg.or(self.format).unwrap_or_default()
+    }
+
+    fn strategy(&self, arg: &StrategyArgs) -> StrategyName {
+        arg.strategy.or(self.strategy).unwrap_or_default()
+    }
+}
+
+impl RulesArgs {
+    fn domain(&self) -> Result<Domain, &'static str> {
+        self.codes.domain()
+    }
+}
+
+impl DomainArgs {
+    fn domain(&self) -> Result<Domain, &'static str> {
+        Domain::new(self.digits, self.max_value)
+    }
+}
+
+impl ScenarioArgs {
+    fn solve(&self, catalogue: &Catalogue) -> Result<Vec<Solution>, &'static str> {
+        let builder = ScenarioBuilder::new()
+            .domain(self.rules.domain()?)
+            .catalogue(catalogue.clone())
+            .mode(self.rules.mode);
+        self.constraints
+            .iter()
+            .cloned()
+            .fold(builder, ScenarioBuilder::verifier)
+            .solve()
+            .map_err(|err| {
+                eprintln!("{}", err);
+                "Invalid scenario"
             })
+    }
+}
+
+impl DealArgs {
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        }
+    }
+
+    fn deal(&self, rng: &mut StdRng, catalogue: &Catalogue) -> Result<Puzzle, &'static str> {
+        if !verifier_limits(self.rules.mode).contains(&self.verifiers) {
+            eprintln!(
+                "{}",
+                ScenarioError::VerifierCount {
+                    mode: self.rules.mode,
+                    count: self.verifiers
+                }
+            );
+            return Err("Invalid scenario");
         }
+        puzzle::generate(
+            rng,
+            catalogue,
+            self.rules.domain()?,
+            self.rules.mode,
+            self.verifiers,
+        )
+        .ok_or("Could not deal a solvable puzzle")
     }
 }
 
 fn main() -> Result<(), &'static str> {
     env_logger::init();
     let args = Args::parse();
-    if args.constraints.len() > 6 {
-        return Err("Too many constraints provided");
-    }
-    let constraints_to_filter: HashSet<(u8, u8)> = args
-        .constraints
-        .iter()
-        .filter_map(|cc| {
-            let CardOrConstraintArg::CardConstraint {
-                inverted: true,
-                card_num,
-                id,
-            } = cc
-            else {
-                return None;
-            };
-            Some((*card_num, *id))
-        })
-        .collect();
-    let solutions = turing_solve(
-        args.constraints
-            .iter()
-            .filter_map(|cc| -> Option<Vec<Constraint>> {
-                match *cc {
-                    CardOrConstraintArg::Card(num) => constraints_for_card(num),
-                    CardOrConstraintArg::CardConstraint {
-                        inverted: false,
-                        card_num,
-                        id: constraint_num,
-                    } => constraints_for_card(card_num)
-                        .and_then(|constraints| constraints.get(constraint_num as usize).cloned())
-                        .map(Constraint::into),
-                    CardOrConstraintArg::CardConstraint {
-                        inverted: true,
-                        card_num,
-                        ..
-                    } => constraints_for_card(card_num),
-                }
+    let settings = || Settings::load(args.config.as_ref(), args.style, args.locale);
+    match args.command {
+        None => solve(&settings()?, &args.solve),
+        Some(Command::Solve(solve_args)) => solve(&settings()?, &solve_args),
+        Some(Command::Tree { scenario, tree }) => print_tree(&settings()?, &scenario, &tree),
+        Some(Command::Play {
+            scenario,
+            strategy,
+            answers,
+        }) => {
+            let settings = settings()?;
+            play(
+                start_game(&settings, settings.strategy(&strategy), &scenario, answers)?,
+                settings.wording,
+            )
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui {
+            scenario,
+            strategy,
+            answers,
+        }) => {
+            let settings = settings()?;
+            turing_solve::tui::run(
+                &mut start_game(&settings, settings.strategy(&strategy), &scenario, answers)?,
+                scenario.rules.domain()?,
+                settings.wording,
+            )
+            .map_err(|_| "Could not run the terminal interface")
+        }
+        Some(Command::Cards) => list_cards(&settings()?),
+        Some(Command::Card {
+            number,
+            grid,
+            codes,
+        }) => show_card(number, grid, codes.domain()?, &settings()?),
+        Some(Command::CheckCards { cards }) => check_catalogue(cards),
+        Some(Command::Generate { deal, reveal }) => {
+            let puzzle = deal.deal(&mut deal.rng(), &settings()?.catalogue)?;
+            println!("{}", puzzle);
+            if reveal {
+                println!("{}", puzzle.secret);
+            }
+            Ok(())
+        }
+        Some(Command::Rate { scenario, strategy }) => {
+            let settings = settings()?;
+            let solutions = scenario.solve(&settings.catalogue)?;
+            if solutions.is_empty() {
+                return Err("No possible solutions");
+            }
+            let (tree, incomplete) =
+                build_tree(&settings, settings.strategy(&strategy), &solutions);
+            print!("{}", Rating::new(&tree));
+            match incomplete {
+                true => Err("Decision tree cannot distinguish all solutions"),
+                false => Ok(()),
+            }
+        }
+        Some(Command::Simulate {
+            deal,
+            strategy,
+            games,
+            puzzles,
+            format,
+        }) => {
+            let settings = settings()?;
+            simulate(
+                &settings,
+                settings.strategy(&strategy),
+                &deal,
+                games,
+                puzzles,
+                settings.format(format),
+            )
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve {
+            address,
+            strategy,
+            max_sessions,
+            idle_minutes,
+            allow_origins,
+        }) => {
+            let settings = settings()?;
+            let api = turing_solve::server::Api::new()
+                .strategy(settings.strategy(&strategy))
+                .max_sessions(max_sessions)
+                .idle(std::time::Duration::from_secs(
+                    idle_minutes.saturating_mul(60),
+                ))
+                .catalogue(settings.catalogue);
+            let api = allow_origins
+                .into_iter()
+                .fold(api, turing_solve::server::Api::allow_origin);
+            println!("Listening on http://{}", address);
+            turing_solve::server::serve(&address, api).map_err(|err| {
+                eprintln!("{}", err);
+                "Could not serve the API"
             })
-            .flat_map(|vc| vc)
-            .filter(|cc| !constraints_to_filter.contains(&(cc.id.card, cc.id.idx)))
-            .dedup_by(|x, y| x.id.eq(&y.id))
-            .collect(),
-    );
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(
+                shell,
+                &mut Args::command(),
+                "turing-solve",
+                &mut std::io::stdout(),
+            );
+            if shell == Shell::Fish {
+                print_fish_card_completions();
+            }
+            Ok(())
+        }
+        Some(Command::Man) => clap_mangen::Man::new(Args::command())
+            .render(&mut std::io::stdout())
+            .map_err(|_| "Could not write the man page"),
+    }
+}
+
+fn solve(settings: &Settings, args: &SolveArgs) -> Result<(), &'static str> {
+    let solutions = args.scenario.solve(&settings.catalogue)?;
+    let strategy = settings.strategy(&args.tree.strategy);
+    if let OutputFormat::Json = settings.format(args.tree.format) {
+        return print_json(settings, args, strategy, &solutions);
+    }
     if !solutions.is_empty() {
-        for solution in &solutions {
-            println!("{}", solution)
+        println!("------ Possible solutions -----\n");
+        if args.group {
+            for group in group_by_code(&solutions) {
+                print!("{}", settings.wording.show(&group))
+            }
+        } else {
+            for solution in &solutions {
+                println!("{}", settings.wording.show(solution))
+            }
         }
     } else {
         println!("----- No possible solutions -----");
         return Ok(());
     }
 
-    let Some(decision_tree) = guess_sequence(&solutions) else {
-        return Err("Could not construct decision tree");
-    };
+    if args.grid {
+        let remaining = shrink(&solutions.iter().collect_vec(), &args.answers);
+        println!("\n------ Possible codes -----\n");
+        print!(
+            "{}",
+            CodeGrid::new(
+                args.scenario.rules.domain()?,
+                remaining.iter().map(|s| s.code())
+            )
+        );
+    }
+
+    let (decision_tree, incomplete) = build_tree(settings, strategy, &solutions);
     println!("\n------ Decision tree -----\n");
-    println!("{}", decision_tree);
+    print_decision_tree(settings, &decision_tree, &args.tree);
+
+    if incomplete {
+        return Err("Decision tree cannot distinguish all solutions");
+    }
+
+    Ok(())
+}
+
+fn print_tree(
+    settings: &Settings,
+    scenario: &ScenarioArgs,
+    args: &TreeArgs,
+) -> Result<(), &'static str> {
+    let solutions = scenario.solve(&settings.catalogue)?;
+    if solutions.is_empty() {
+        return Err("No possible solutions");
+    }
+    let (decision_tree, incomplete) =
+        build_tree(settings, settings.strategy(&args.strategy), &solutions);
+    match settings.format(args.format) {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&tree_json(&decision_tree, args.stats)?)
+                .map_err(|_| "Could not serialize output")?
+        ),
+        OutputFormat::Text => print_decision_tree(settings, &decision_tree, args),
+    }
+    if incomplete {
+        return Err("Decision tree cannot distinguish all solutions");
+    }
+    Ok(())
+}
+
+fn print_decision_tree(settings: &Settings, decision_tree: &DecisionTree, args: &TreeArgs) {
+    if args.compact {
+        println!("{}", settings.wording.show(&decision_tree.compact()));
+    } else {
+        println!("{}", settings.wording.show(decision_tree));
+    }
 
+    if args.stats {
+        println!("------ Tree statistics -----\n");
+        print!("{}", decision_tree.stats());
+    }
+}
+
+/// Start a game of the scenario from the answers already given.
+fn start_game(
+    settings: &Settings,
+    strategy: StrategyName,
+    scenario: &ScenarioArgs,
+    answers: Vec<Guess>,
+) -> Result<Game, &'static str> {
+    let solutions = scenario.solve(&settings.catalogue)?;
+    if solutions.is_empty() {
+        return Err("No possible solutions");
+    }
+    let (tree, _) = build_tree(settings, strategy, &solutions);
+    let mut game = Game::new(tree);
+    for answer in answers {
+        game.record(answer);
+    }
+    Ok(game)
+}
+
+fn play(mut game: Game, wording: Wording) -> Result<(), &'static str> {
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        let position = game.position();
+        if let Some(code) = position.solution() {
+            println!("The code is {} ({} queries)", code, game.answers().len());
+            return Ok(());
+        }
+        let Some(constraint) = position.next_guess else {
+            if position.candidates.is_empty() {
+                return Err("The answers rule out every solution");
+            }
+            println!("Still possible: {}", position.candidates.iter().join(" "));
+            return Err("Decision tree cannot distinguish all solutions");
+        };
+        let name = game
+            .tree()
+            .constraint(constraint)
+            .map(|c| wording.name(c))
+            .unwrap_or_default();
+        print!(
+            "{} codes left. Is {} {} verified? [y/n] ",
+            position.candidates.len(),
+            constraint,
+            name
+        );
+        std::io::stdout()
+            .flush()
+            .map_err(|_| "Could not write the question")?;
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        match line.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => {
+                game.answer(true);
+            }
+            "n" | "no" => {
+                game.answer(false);
+            }
+            "u" | "undo" => {
+                game.undo();
+            }
+            "q" | "quit" => return Ok(()),
+            _ => println!("Answer y, n, u to undo or q to quit"),
+        }
+    }
+}
+
+fn simulate(
+    settings: &Settings,
+    strategy: StrategyName,
+    deal: &DealArgs,
+    games: usize,
+    puzzles: Option<PathBuf>,
+    format: OutputFormat,
+) -> Result<(), &'static str> {
+    let mut rng = deal.rng();
+    let puzzles = match puzzles {
+        Some(path) => load_puzzles(&path, deal, &mut rng, &settings.catalogue)?,
+        None => (0..games)
+            .map(|_| deal.deal(&mut rng, &settings.catalogue))
+            .collect::<Result<_, _>>()?,
+    };
+    let mut simulation = Simulation::default();
+    for (idx, puzzle) in puzzles.iter().enumerate() {
+        let picker = strategy.picker(deal.seed.map(|seed| seed.wrapping_add(idx as u64)));
+        let tree = guess_sequence_with(&puzzle.solutions, picker)
+            .unwrap_or_else(|incomplete| *incomplete.tree);
+        simulation.play(&tree, &puzzle.secret);
+    }
+    match format {
+        OutputFormat::Text => print!("{}", simulation),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&simulation)
+                .map_err(|_| "Could not serialize the simulation")?
+        ),
+    }
+    Ok(())
+}
+
+/// Read a puzzle file, skipping blank lines and `#` comments.
+fn load_puzzles(
+    path: &PathBuf,
+    deal: &DealArgs,
+    rng: &mut StdRng,
+    catalogue: &Catalogue,
+) -> Result<Vec<Puzzle>, &'static str> {
+    let source = std::fs::read_to_string(path).map_err(|err| {
+        eprintln!("Cannot read {}: {}", path.display(), err);
+        "Invalid puzzle file"
+    })?;
+    let domain = deal.rules.domain()?;
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(idx, line)| {
+            Puzzle::parse(line, rng, catalogue, domain, deal.rules.mode).map_err(|err| {
+                eprintln!("{}:{}: {}", path.display(), idx + 1, err);
+                "Invalid puzzle file"
+            })
+        })
+        .collect()
+}
+
+/// The fish script generated by clap leaves positional arguments
+/// out, so complete cards and constraints separately.
+fn print_fish_card_completions() {
+    let fish = |condition: &str, constraints: bool| {
+        for value in CatalogueValues::new(parse_card_number, constraints)
+            .possible_values()
+            .into_iter()
+            .flatten()
+        {
+            let help = value.get_help().map(|h| h.to_string()).unwrap_or_default();
+            println!(
+                "complete -c turing-solve -n \"{condition}\" -f -a '{}' -d '{}'",
+                value.get_name(),
+                help.replace('\\', "\\\\").replace('\'', "\\'")
+            );
+        }
+    };
+    fish("__fish_turing_solve_needs_command", true);
+    for command in ["solve", "tree", "play", "tui", "rate"] {
+        fish(
+            &format!("__fish_turing_solve_using_subcommand {command}"),
+            true,
+        );
+    }
+    fish("__fish_turing_solve_using_subcommand card", false);
+}
+
+fn check_catalogue(cards: Option<PathBuf>) -> Result<(), &'static str> {
+    let mut catalogue = Catalogue::new();
+    if let Some(path) = cards {
+        let source = std::fs::read_to_string(&path).map_err(|err| {
+            eprintln!("Cannot read {}: {}", path.display(), err);
+            "Invalid card file"
+        })?;
+        catalogue.load(&source).map_err(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            "Invalid card file"
+        })?;
+    }
+    let issues = catalogue.check();
+    if issues.is_empty() {
+        println!("All cards are consistent");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    Err("Card catalogue is inconsistent")
+}
+
+/// A constraint as written on its card, for completions.
+fn card_text(constraint: &Constraint) -> String {
+    Wording::default().name(constraint).into_owned()
+}
+
+fn list_cards(settings: &Settings) -> Result<(), &'static str> {
+    for number in settings.catalogue.numbers() {
+        let constraints = settings
+            .catalogue
+            .constraints(number)
+            .ok_or("Unknown card number")?;
+        println!(
+            "{:>2}: {}",
+            number,
+            constraints
+                .iter()
+                .map(|c| settings.wording.name(c))
+                .join(" | ")
+        );
+    }
+    Ok(())
+}
+
+fn show_card(
+    number: u8,
+    grid: bool,
+    domain: Domain,
+    settings: &Settings,
+) -> Result<(), &'static str> {
+    let wording = settings.wording;
+    let constraints = settings
+        .catalogue
+        .constraints(number)
+        .ok_or("Unknown card number")?;
+    println!("------ Card {} -----", number);
+    for constraint in &constraints {
+        let codes = constraint.accepted_codes(domain).collect_vec();
+        println!("\n{} ({} codes)", wording.show(constraint), codes.len());
+        if grid {
+            print!("{}", CodeGrid::new(domain, codes));
+            continue;
+        }
+        for row in codes.chunks(15) {
+            println!("\t{}", row.iter().join(" "));
+        }
+    }
+    Ok(())
+}
+
+fn decision_tree(
+    strategy: StrategyName,
+    solutions: &[Solution],
+) -> Result<DecisionTree, IncompleteTree> {
+    guess_sequence_with(solutions, strategy.picker(None))
+}
+
+/// Build the decision tree, reporting any solutions it cannot
+/// distinguish and whether it is incomplete.
+fn build_tree(
+    settings: &Settings,
+    strategy: StrategyName,
+    solutions: &[Solution],
+) -> (DecisionTree, bool) {
+    match decision_tree(strategy, solutions) {
+        Ok(tree) => (tree, false),
+        Err(incomplete) => {
+            eprint!("{}", settings.wording.show(&incomplete));
+            (*incomplete.tree, true)
+        }
+    }
+}
+
+/// The decision tree, and its statistics if requested, as JSON.
+fn tree_json(
+    decision_tree: &DecisionTree,
+    stats: bool,
+) -> Result<serde_json::Map<String, serde_json::Value>, &'static str> {
+    let mut output = serde_json::Map::new();
+    output.insert(
+        "tree".into(),
+        serde_json::to_value(decision_tree.to_outcome())
+            .map_err(|_| "Could not serialize decision tree")?,
+    );
+    if stats {
+        output.insert(
+            "stats".into(),
+            serde_json::to_value(decision_tree.stats())
+                .map_err(|_| "Could not serialize tree statistics")?,
+        );
+    }
+    Ok(output)
+}
+
+fn print_json(
+    settings: &Settings,
+    args: &SolveArgs,
+    strategy: StrategyName,
+    solutions: &[Solution],
+) -> Result<(), &'static str> {
+    let mut output = serde_json::Map::new();
+    output.insert(
+        "solutions".into(),
+        if args.group {
+            serde_json::to_value(group_by_code(solutions))
+        } else {
+            serde_json::to_value(solutions)
+        }
+        .map_err(|_| "Could not serialize solutions")?,
+    );
+    let mut incomplete = false;
+    if !solutions.is_empty() {
+        let (decision_tree, is_incomplete) = build_tree(settings, strategy, solutions);
+        incomplete = is_incomplete;
+        output.extend(tree_json(&decision_tree, args.tree.stats)?);
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).map_err(|_| "Could not serialize output")?
+    );
+    if incomplete {
+        return Err("Decision tree cannot distinguish all solutions");
+    }
     Ok(())
 }
@@ -0,0 +1,433 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use rand::{
+    Rng,
+    seq::{IndexedRandom, IteratorRandom},
+};
+
+use serde::{Serialize, ser::SerializeStruct};
+
+use crate::{
+    catalogue::Catalogue,
+    guess_sequence::{DecisionTree, Guess},
+    scenario::code::Code,
+    solver::{
+        Domain, GameMode, ScenarioBuilder, ScenarioError, Selection, Solution, VerifierSpec,
+        verifier_limits,
+    },
+};
+
+/// How many sets of cards [`generate`] deals before giving up.
+const ATTEMPTS: usize = 10_000;
+
+/// Verifier cards dealt for a game, with the solution picked as secret.
+pub struct Puzzle {
+    pub verifiers: Vec<VerifierSpec>,
+    /// Every solution the verifiers allow.
+    pub solutions: Vec<Solution>,
+    pub secret: Solution,
+}
+
+/// Written as the verifiers to pass to the solver, e.g. `3 10 14 17`.
+impl std::fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.verifiers.iter().join(" "))
+    }
+}
+
+/// Why a puzzle cannot be read.
+#[derive(Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    Syntax(&'static str),
+    Scenario(ScenarioError),
+    NoSolution,
+    /// No solution has the code given as secret.
+    UnknownSecret(Code),
+}
+
+impl std::fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleError::Syntax(error) => f.write_str(error),
+            PuzzleError::Scenario(error) => write!(f, "{error}"),
+            PuzzleError::NoSolution => write!(f, "The verifiers have no solution"),
+            PuzzleError::UnknownSecret(code) => write!(f, "No solution has the code {code}"),
+        }
+    }
+}
+
+impl std::fmt::Debug for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl Puzzle {
+    /// Read a puzzle written as the verifiers to pass to the solver,
+    /// optionally followed by `=` and the code of the secret, e.g.
+    /// `3 10 14 17 =122`. Without a code, the secret is picked at
+    /// random among the solutions.
+    pub fn parse(
+        line: &str,
+        rng: &mut impl Rng,
+        catalogue: &Catalogue,
+        domain: Domain,
+        mode: Option<GameMode>,
+    ) -> Result<Puzzle, PuzzleError> {
+        let mut verifiers = vec![];
+        let mut code = None;
+        for word in line.split_whitespace() {
+            match word.strip_prefix('=') {
+                Some(secret) => code = Some(secret.parse().map_err(PuzzleError::Syntax)?),
+                None => verifiers.push(word.parse().map_err(PuzzleError::Syntax)?),
+            }
+        }
+        let solutions =
+            solve(&verifiers, catalogue, domain, mode).map_err(PuzzleError::Scenario)?;
+        let candidates = solutions
+            .iter()
+            .filter(|s| code.is_none_or(|code| s.code() == code))
+            .collect_vec();
+        let secret = match (candidates.choose(rng), code) {
+            (Some(&secret), _) => secret.clone(),
+            (None, Some(code)) => return Err(PuzzleError::UnknownSecret(code)),
+            (None, None) => return Err(PuzzleError::NoSolution),
+        };
+        Ok(Puzzle {
+            verifiers,
+            solutions,
+            secret,
+        })
+    }
+}
+
+fn solve(
+    verifiers: &[VerifierSpec],
+    catalogue: &Catalogue,
+    domain: Domain,
+    mode: Option<GameMode>,
+) -> Result<Vec<Solution>, ScenarioError> {
+    let builder = ScenarioBuilder::new()
+        .domain(domain)
+        .catalogue(catalogue.clone())
+        .mode(mode);
+    verifiers
+        .iter()
+        .cloned()
+        .fold(builder, ScenarioBuilder::verifier)
+        .solve()
+}
+
+/// Deal random sets of verifier cards until they leave more than one
+/// code to tell apart, and pick one of their solutions as secret.
+///
+/// Returns `None` if the game mode, or any game without one, does not
+/// allow that many verifiers or no puzzle turns up after a fair number of deals.
+pub fn generate(
+    rng: &mut impl Rng,
+    catalogue: &Catalogue,
+    domain: Domain,
+    mode: Option<GameMode>,
+    verifiers: usize,
+) -> Option<Puzzle> {
+    if !verifier_limits(mode).contains(&verifiers) {
+        return None;
+    }
+    (0..ATTEMPTS).find_map(|_| {
+        let verifiers = catalogue
+            .numbers()
+            .sample(rng, verifiers)
+            .into_iter()
+            .sorted()
+            .map(|card| VerifierSpec {
+                card,
+                selection: Selection::All,
+            })
+            .collect_vec();
+        let solutions = solve(&verifiers, catalogue, domain, mode).ok()?;
+        if solutions.iter().map(|s| s.code()).all_equal() {
+            return None;
+        }
+        let secret = solutions.choose(rng)?.clone();
+        Some(Puzzle {
+            verifiers,
+            solutions,
+            secret,
+        })
+    })
+}
+
+/// Estimate the rounds the answers would take by packing consecutive
+/// constraint queries into rounds of up to three distinct verifiers,
+/// as a round of the game tests one proposal on up to three verifiers.
+///
+/// No proposal code is planned, so a real game may need more rounds
+/// or, with proposals testing several queries at once, fewer.
+pub fn rounds(answers: &[Guess]) -> usize {
+    let mut rounds = 0;
+    let mut cards = vec![];
+    for answer in answers {
+        if cards.is_empty() || cards.len() == 3 || cards.contains(&answer.constraint.card) {
+            rounds += 1;
+            cards.clear();
+        }
+        cards.push(answer.constraint.card);
+    }
+    rounds
+}
+
+/// How many queries or estimated rounds a series of games took.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub average: f64,
+    pub worst: usize,
+    /// The number of games for each count.
+    pub games: BTreeMap<usize, usize>,
+}
+
+impl Distribution {
+    fn new(counts: &[usize]) -> Self {
+        Distribution {
+            average: match counts.len() {
+                0 => 0.0,
+                len => counts.iter().sum::<usize>() as f64 / len as f64,
+            },
+            worst: counts.iter().copied().max().unwrap_or(0),
+            games: counts.iter().copied().counts().into_iter().collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:.2} average, {} worst case", self.average, self.worst)?;
+        let total = self.games.values().sum::<usize>().max(1);
+        for (count, games) in &self.games {
+            writeln!(
+                f,
+                "\t{count}: {games} ({:.1}%)",
+                100.0 * *games as f64 / total as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The constraint queries needed to find the code in a series of
+/// games, and the rounds they are estimated to take.
+#[derive(Clone, Debug, Default)]
+pub struct Simulation {
+    pub queries: Vec<usize>,
+    /// Estimated with [`rounds`].
+    pub rounds: Vec<usize>,
+    /// Games that ended without the tree finding the code.
+    pub unsolved: usize,
+}
+
+impl Simulation {
+    /// Play a game against the secret, following the tree.
+    /// The secret answers each query as the verifiers would.
+    pub fn play(&mut self, tree: &DecisionTree, secret: &Solution) {
+        let answers = tree.play(secret);
+        if tree.position(&answers).solution() != Some(secret.code()) {
+            self.unsolved += 1;
+        }
+        self.queries.push(answers.len());
+        self.rounds.push(rounds(&answers));
+    }
+
+    pub fn games(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn queries(&self) -> Distribution {
+        Distribution::new(&self.queries)
+    }
+
+    pub fn rounds(&self) -> Distribution {
+        Distribution::new(&self.rounds)
+    }
+}
+
+/// Serialized as a summary rather than game by game.
+impl Serialize for Simulation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Simulation", 4)?;
+        state.serialize_field("games", &self.games())?;
+        state.serialize_field("unsolved", &self.unsolved)?;
+        state.serialize_field("queries", &self.queries())?;
+        state.serialize_field("estimated_rounds", &self.rounds())?;
+        state.end()
+    }
+}
+
+impl std::fmt::Display for Simulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Games: {} ({} unsolved)", self.games(), self.unsolved)?;
+        write!(f, "Queries: {}", self.queries())?;
+        write!(f, "Estimated rounds: {}", self.rounds())
+    }
+}
+
+/// How hard a scenario is to solve, by the average queries its
+/// decision tree needs. The thresholds split the puzzles [`generate`]
+/// deals with four verifiers into rough thirds, as measured over
+/// 6000 deals with the balance strategy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    /// Under three queries on average.
+    Easy,
+    /// Under 4.3 queries on average.
+    Standard,
+    Hard,
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Standard => "standard",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+/// How much a scenario leaves open and how many
+/// queries its decision tree needs to settle it.
+pub struct Rating {
+    pub solutions: usize,
+    pub codes: usize,
+    /// A game against each solution.
+    pub simulation: Simulation,
+}
+
+impl Rating {
+    pub fn new(tree: &DecisionTree) -> Self {
+        let mut simulation = Simulation::default();
+        for secret in &tree.solutions {
+            simulation.play(tree, secret);
+        }
+        Rating {
+            solutions: tree.solutions.len(),
+            codes: tree.solutions.iter().map(|s| s.code()).unique().count(),
+            simulation,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        match self.simulation.queries().average {
+            average if average < 3.0 => Difficulty::Easy,
+            average if average < 4.3 => Difficulty::Standard,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Difficulty: {}", self.difficulty())?;
+        writeln!(f, "Solutions: {}", self.solutions)?;
+        writeln!(f, "Codes: {}", self.codes)?;
+        let queries = self.simulation.queries();
+        writeln!(f, "Average queries: {:.2}", queries.average)?;
+        writeln!(f, "Worst case queries: {}", queries.worst)
+    }
+}
+
+#[test]
+fn generate_deals_solvable_puzzles() {
+    use rand::{SeedableRng, rngs::StdRng};
+    let deal = |seed| {
+        generate(
+            &mut StdRng::seed_from_u64(seed),
+            &Catalogue::new(),
+            Domain::default(),
+            Some(GameMode::Classic),
+            4,
+        )
+        .unwrap()
+    };
+    let puzzle = deal(7);
+    assert_eq!(puzzle.verifiers.len(), 4);
+    assert_eq!(puzzle.to_string(), deal(7).to_string());
+    let solutions = crate::solver::turing_solve(
+        puzzle
+            .verifiers
+            .iter()
+            .flat_map(|spec| crate::solver::constraints_for_card(spec.card).unwrap())
+            .collect(),
+    );
+    assert_eq!(solutions.len(), puzzle.solutions.len());
+    assert!(!puzzle.solutions.iter().map(|s| s.code()).all_equal());
+    assert!(solutions.iter().any(|s| s.code() == puzzle.secret.code()));
+    assert!(
+        generate(
+            &mut StdRng::seed_from_u64(7),
+            &Catalogue::new(),
+            Domain::default(),
+            Some(GameMode::Classic),
+            3
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn rating_plays_every_solution() {
+    let tree = crate::guess_sequence::example_tree();
+    let rating = Rating::new(&tree);
+    assert_eq!(rating.simulation.games(), tree.solutions.len());
+    assert_eq!(rating.simulation.unsolved, 0);
+    assert!(rating.simulation.queries().worst <= tree.worst_case_path_length());
+    assert_eq!(rating.difficulty(), Difficulty::Standard);
+    assert_eq!(rating.to_string().lines().count(), 5);
+}
+
+#[test]
+fn puzzle_parse_picks_the_secret() {
+    use rand::{SeedableRng, rngs::StdRng};
+    let parse = |line| {
+        Puzzle::parse(
+            line,
+            &mut StdRng::seed_from_u64(1),
+            &Catalogue::new(),
+            Domain::default(),
+            None,
+        )
+    };
+    let puzzle = parse("3 10 14 17.0,2 =122").unwrap();
+    assert_eq!(puzzle.to_string(), "3 10 14 17.0,2");
+    assert_eq!(puzzle.secret.code().to_string(), "122");
+    assert!(parse("3 10 14 17").is_ok());
+    assert_eq!(
+        parse("3 10 14 17 =555").err(),
+        Some(PuzzleError::UnknownSecret("555".parse().unwrap()))
+    );
+    assert!(matches!(parse("3 10 x 17"), Err(PuzzleError::Syntax(_))));
+    assert!(matches!(parse("3 10 32"), Err(PuzzleError::Scenario(_))));
+}
+
+#[test]
+fn rounds_group_queries_to_distinct_verifiers() {
+    let answers = |ids: &str| {
+        ids.split(' ')
+            .map(|id| id.parse::<Guess>().unwrap())
+            .collect_vec()
+    };
+    assert_eq!(rounds(&[]), 0);
+    assert_eq!(rounds(&answers("3.0 !10.1 14.0")), 1);
+    assert_eq!(rounds(&answers("3.0 !10.1 14.0 17.2")), 2);
+    assert_eq!(rounds(&answers("3.0 !3.1 10.0")), 2);
+    let simulation = Simulation {
+        queries: vec![2, 4, 4],
+        rounds: vec![1, 2, 2],
+        unsolved: 0,
+    };
+    assert_eq!(
+        simulation.to_string(),
+        "Games: 3 (0 unsolved)\n\
+         Queries: 3.33 average, 4 worst case\n\t2: 1 (33.3%)\n\t4: 2 (66.7%)\n\
+         Estimated rounds: 1.67 average, 2 worst case\n\t1: 1 (33.3%)\n\t2: 2 (66.7%)\n"
+    );
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::scenario::{code::Code, domain::Domain};
+
+/// The code space drawn as one grid per blue value, with
+/// yellow values as rows and purple values as columns.
+///
+/// Codes with more than three digits are marked when any
+/// code sharing their first three digits is.
+pub struct CodeGrid {
+    max_value: u8,
+    marked: HashSet<[u8; 3]>,
+}
+
+impl CodeGrid {
+    /// A grid of the domain marking the provided codes.
+    pub fn new(domain: Domain, codes: impl IntoIterator<Item = Code>) -> Self {
+        CodeGrid {
+            max_value: domain.max_value(),
+            marked: codes
+                .into_iter()
+                .map(|code| [code.blue(), code.yellow(), code.purple()])
+                .collect(),
+        }
+    }
+
+    /// Number of marked cells.
+    pub fn len(&self) -> usize {
+        self.marked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marked.is_empty()
+    }
+
+    fn lines(&self, blue: u8) -> Vec<String> {
+        let values = 1..=self.max_value;
+        let mut lines = vec![
+            format!("B = {blue}"),
+            format!("Y\\P {}", values.clone().join(" ")),
+        ];
+        lines.extend(values.clone().map(|yellow| {
+            format!(
+                " {yellow}  {}",
+                values
+                    .clone()
+                    .map(
+                        |purple| match self.marked.contains(&[blue, yellow, purple]) {
+                            true => '#',
+                            false => '.',
+                        }
+                    )
+                    .join(" ")
+            )
+        }));
+        lines
+    }
+}
+
+impl std::fmt::Display for CodeGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let grids = (1..=self.max_value)
+            .map(|blue| self.lines(blue))
+            .collect_vec();
+        let width = 4 + 2 * self.max_value as usize;
+        for row in 0..grids[0].len() {
+            let line = grids
+                .iter()
+                .map(|lines| format!("{:width$}", lines[row]))
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn code_grid_display() {
+    let domain = Domain::new(3, 2).unwrap();
+    let grid = CodeGrid::new(domain, ["112", "221"].map(|c| c.parse().unwrap()));
+    assert_eq!(grid.len(), 2);
+    assert_eq!(
+        grid.to_string(),
+        "B = 1     B = 2\n\
+         Y\\P 1 2   Y\\P 1 2\n \
+         1  . #    1  . .\n \
+         2  . .    2  # .\n"
+    );
+}
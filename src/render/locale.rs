@@ -0,0 +1,253 @@
+use serde::{Deserialize, de::Error};
+
+use crate::scenario::constraint::ConstraintID;
+
+/// A language to display constraints in.
+///
+/// Constraints built only from colours, digits and
+/// comparisons read the same in every locale.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+    De,
+}
+
+impl Locale {
+    /// The wording of the identified constraint on the cards
+    /// of this locale, if it differs from the English one.
+    pub fn text(self, id: ConstraintID) -> Option<&'static str> {
+        let table = match self {
+            Locale::En => return None,
+            Locale::Fr => FR,
+            Locale::De => DE,
+        };
+        table
+            .iter()
+            .find(|((card, idx), _)| id.card == *card && id.idx == *idx)
+            .map(|(_, text)| *text)
+    }
+
+    /// Names of the blue, yellow and purple digits.
+    pub fn colours(self) -> [&'static str; 3] {
+        match self {
+            Locale::En => ["blue", "yellow", "purple"],
+            Locale::Fr => ["bleu", "jaune", "violet"],
+            Locale::De => ["blau", "gelb", "lila"],
+        }
+    }
+
+    /// Prefix marking a guess answered as unverified
+    /// when colours are spelled out.
+    pub fn negation(self) -> &'static str {
+        match self {
+            Locale::En => "not ",
+            Locale::Fr => "pas ",
+            Locale::De => "nicht ",
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "fr" => Ok(Locale::Fr),
+            "de" => Ok(Locale::De),
+            _ => Err("Locale must be one of en, fr or de"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Locale {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+const FR: &[((u8, u8), &str)] = &[
+    ((5, 0), "🔷 est pair"),
+    ((5, 1), "🔷 est impair"),
+    ((6, 0), "🟨 est pair"),
+    ((6, 1), "🟨 est impair"),
+    ((7, 0), "🟣 est pair"),
+    ((7, 1), "🟣 est impair"),
+    ((8, 0), "Aucun 1"),
+    ((8, 1), "Un seul 1"),
+    ((8, 2), "Deux 1"),
+    ((8, 3), "Trois 1"),
+    ((9, 0), "Aucun 3"),
+    ((9, 1), "Un seul 3"),
+    ((9, 2), "Deux 3"),
+    ((9, 3), "Trois 3"),
+    ((10, 0), "Aucun 4"),
+    ((10, 1), "Un seul 4"),
+    ((10, 2), "Deux 4"),
+    ((10, 3), "Trois 4"),
+    ((14, 0), "🔷 le plus petit"),
+    ((14, 1), "🟨 le plus petit"),
+    ((14, 2), "🟣 le plus petit"),
+    ((15, 0), "🔷 le plus grand"),
+    ((15, 1), "🟨 le plus grand"),
+    ((15, 2), "🟣 le plus grand"),
+    ((16, 0), "Pairs > Impairs"),
+    ((16, 1), "Impairs > Pairs"),
+    ((17, 0), "Aucun chiffre pair"),
+    ((17, 1), "1 chiffre pair"),
+    ((17, 2), "2 chiffres pairs"),
+    ((17, 3), "3 chiffres pairs"),
+    ((18, 0), "Somme paire"),
+    ((18, 1), "Somme impaire"),
+    ((20, 0), "Triplet"),
+    ((20, 1), "Doublet"),
+    ((20, 2), "Aucune répétition"),
+    ((21, 0), "Une paire"),
+    ((21, 1), "Aucune paire"),
+    ((22, 0), "Croissant"),
+    ((22, 1), "Décroissant"),
+    ((22, 2), "Aucun ordre"),
+    ((23, 0), "Somme < 6"),
+    ((23, 1), "Somme = 6"),
+    ((23, 2), "Somme > 6"),
+    ((24, 0), "3 en suite croissante"),
+    ((24, 1), "2 en suite croissante"),
+    ((24, 2), "Aucune suite croissante"),
+    ((25, 0), "Aucune suite"),
+    ((25, 1), "2 en suite"),
+    ((25, 2), "3 en suite"),
+    ((33, 0), "🔷 est pair"),
+    ((33, 1), "🟨 est pair"),
+    ((33, 2), "🟣 est pair"),
+    ((33, 3), "🔷 est impair"),
+    ((33, 4), "🟨 est impair"),
+    ((33, 5), "🟣 est impair"),
+    ((36, 0), "Somme multiple de 3"),
+    ((36, 1), "Somme multiple de 4"),
+    ((36, 2), "Somme multiple de 5"),
+    ((42, 0), "🔷 le plus petit"),
+    ((42, 1), "🟨 le plus petit"),
+    ((42, 2), "🟣 le plus petit"),
+    ((42, 3), "🔷 le plus grand"),
+    ((42, 4), "🟨 le plus grand"),
+    ((42, 5), "🟣 le plus grand"),
+    ((45, 0), "Aucun 1"),
+    ((45, 1), "Un seul 1"),
+    ((45, 2), "Deux 1"),
+    ((45, 3), "Aucun 3"),
+    ((45, 4), "Un seul 3"),
+    ((45, 5), "Deux 3"),
+    ((46, 0), "Aucun 3"),
+    ((46, 1), "Un seul 3"),
+    ((46, 2), "Deux 3"),
+    ((46, 3), "Aucun 4"),
+    ((46, 4), "Un seul 4"),
+    ((46, 5), "Deux 4"),
+    ((47, 0), "Aucun 1"),
+    ((47, 1), "Un seul 1"),
+    ((47, 2), "Deux 1"),
+    ((47, 3), "Aucun 4"),
+    ((47, 4), "Un seul 4"),
+    ((47, 5), "Deux 4"),
+];
+
+const DE: &[((u8, u8), &str)] = &[
+    ((5, 0), "🔷 ist gerade"),
+    ((5, 1), "🔷 ist ungerade"),
+    ((6, 0), "🟨 ist gerade"),
+    ((6, 1), "🟨 ist ungerade"),
+    ((7, 0), "🟣 ist gerade"),
+    ((7, 1), "🟣 ist ungerade"),
+    ((8, 0), "Keine 1"),
+    ((8, 1), "Genau eine 1"),
+    ((8, 2), "Zwei 1"),
+    ((8, 3), "Drei 1"),
+    ((9, 0), "Keine 3"),
+    ((9, 1), "Genau eine 3"),
+    ((9, 2), "Zwei 3"),
+    ((9, 3), "Drei 3"),
+    ((10, 0), "Keine 4"),
+    ((10, 1), "Genau eine 4"),
+    ((10, 2), "Zwei 4"),
+    ((10, 3), "Drei 4"),
+    ((14, 0), "🔷 am kleinsten"),
+    ((14, 1), "🟨 am kleinsten"),
+    ((14, 2), "🟣 am kleinsten"),
+    ((15, 0), "🔷 am größten"),
+    ((15, 1), "🟨 am größten"),
+    ((15, 2), "🟣 am größten"),
+    ((16, 0), "Gerade > Ungerade"),
+    ((16, 1), "Ungerade > Gerade"),
+    ((17, 0), "Keine gerade Zahl"),
+    ((17, 1), "1 gerade Zahl"),
+    ((17, 2), "2 gerade Zahlen"),
+    ((17, 3), "3 gerade Zahlen"),
+    ((18, 0), "Summe gerade"),
+    ((18, 1), "Summe ungerade"),
+    ((20, 0), "Drilling"),
+    ((20, 1), "Zwilling"),
+    ((20, 2), "Keine Wiederholung"),
+    ((21, 0), "Ein Paar"),
+    ((21, 1), "Kein Paar"),
+    ((22, 0), "Aufsteigend"),
+    ((22, 1), "Absteigend"),
+    ((22, 2), "Keine Ordnung"),
+    ((23, 0), "Summe < 6"),
+    ((23, 1), "Summe = 6"),
+    ((23, 2), "Summe > 6"),
+    ((24, 0), "3 in aufsteigender Folge"),
+    ((24, 1), "2 in aufsteigender Folge"),
+    ((24, 2), "Keine aufsteigende Folge"),
+    ((25, 0), "Keine Folge"),
+    ((25, 1), "2 in Folge"),
+    ((25, 2), "3 in Folge"),
+    ((33, 0), "🔷 ist gerade"),
+    ((33, 1), "🟨 ist gerade"),
+    ((33, 2), "🟣 ist gerade"),
+    ((33, 3), "🔷 ist ungerade"),
+    ((33, 4), "🟨 ist ungerade"),
+    ((33, 5), "🟣 ist ungerade"),
+    ((36, 0), "Summe Vielfaches von 3"),
+    ((36, 1), "Summe Vielfaches von 4"),
+    ((36, 2), "Summe Vielfaches von 5"),
+    ((42, 0), "🔷 am kleinsten"),
+    ((42, 1), "🟨 am kleinsten"),
+    ((42, 2), "🟣 am kleinsten"),
+    ((42, 3), "🔷 am größten"),
+    ((42, 4), "🟨 am größten"),
+    ((42, 5), "🟣 am größten"),
+    ((45, 0), "Keine 1"),
+    ((45, 1), "Genau eine 1"),
+    ((45, 2), "Zwei 1"),
+    ((45, 3), "Keine 3"),
+    ((45, 4), "Genau eine 3"),
+    ((45, 5), "Zwei 3"),
+    ((46, 0), "Keine 3"),
+    ((46, 1), "Genau eine 3"),
+    ((46, 2), "Zwei 3"),
+    ((46, 3), "Keine 4"),
+    ((46, 4), "Genau eine 4"),
+    ((46, 5), "Zwei 4"),
+    ((47, 0), "Keine 1"),
+    ((47, 1), "Genau eine 1"),
+    ((47, 2), "Zwei 1"),
+    ((47, 3), "Keine 4"),
+    ((47, 4), "Genau eine 4"),
+    ((47, 5), "Zwei 4"),
+];
+
+#[test]
+fn locale_text() {
+    let id = |card, idx| ConstraintID { card, idx };
+    assert_eq!(Locale::Fr.text(id(17, 2)), Some("2 chiffres pairs"));
+    assert_eq!(Locale::De.text(id(14, 1)), Some("🟨 am kleinsten"));
+    assert_eq!(Locale::De.text(id(2, 0)), None);
+    assert_eq!(Locale::En.text(id(17, 2)), None);
+    assert!(FR.iter().chain(DE).all(|((card, idx), _)| {
+        crate::solver::constraints_for_card(*card).is_some_and(|c| c.len() > *idx as usize)
+    }));
+}
@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, de::Error};
+
+use crate::scenario::constraint::Constraint;
+
+mod grid;
+mod locale;
+
+pub use grid::CodeGrid;
+pub use locale::Locale;
+
+/// How constraint names are written out.
+///
+/// The cards use coloured shapes for the code digits,
+/// which not every terminal or screen reader handles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Style {
+    /// The symbols printed on the cards, e.g. `🔷 < 3`.
+    #[default]
+    Emoji,
+    /// Colour initials, e.g. `B < 3`.
+    Ascii,
+    /// Spelled-out colours, e.g. `blue < 3`.
+    Words,
+}
+
+/// How constraints are written out: the symbols standing for the
+/// code digits and the language of the cards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Wording {
+    pub style: Style,
+    pub locale: Locale,
+}
+
+impl Wording {
+    pub fn new(style: Style, locale: Locale) -> Self {
+        Wording { style, locale }
+    }
+
+    /// A constraint as written on its card.
+    pub fn name(self, constraint: &Constraint) -> Cow<'_, str> {
+        self.style.name(constraint.text(self.locale), self.locale)
+    }
+
+    /// Prefix marking a guess answered as unverified.
+    pub fn negation(self) -> &'static str {
+        self.style.negation(self.locale)
+    }
+
+    /// Display a value in this wording.
+    pub fn show<T: Render + ?Sized>(self, value: &T) -> Shown<'_, T> {
+        Shown {
+            value,
+            wording: self,
+        }
+    }
+}
+
+/// Values whose display depends on the [`Wording`].
+///
+/// Their `Display` implementation uses the default wording,
+/// while [`Wording::show`] displays them in any other.
+pub trait Render {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result;
+}
+
+/// A value displayed in a chosen [`Wording`].
+pub struct Shown<'a, T: ?Sized> {
+    value: &'a T,
+    wording: Wording,
+}
+
+impl<T: Render + ?Sized> std::fmt::Display for Shown<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.render(f, self.wording)
+    }
+}
+
+const COLOURS: [(&str, &str); 3] = [("🔷", "B"), ("🟨", "Y"), ("🟣", "P")];
+
+impl Style {
+    /// Rewrite a constraint name from the cards in this style,
+    /// spelling colours out in the provided locale.
+    pub fn name(self, name: &str, locale: Locale) -> Cow<'_, str> {
+        if self == Style::Emoji {
+            return Cow::Borrowed(name);
+        }
+        Cow::Owned(COLOURS.iter().zip(locale.colours()).fold(
+            name.to_string(),
+            |name, ((emoji, ascii), word)| {
+                name.replace(emoji, if self == Style::Ascii { ascii } else { word })
+            },
+        ))
+    }
+
+    /// Prefix marking a guess answered as unverified.
+    pub fn negation(self, locale: Locale) -> &'static str {
+        match self {
+            Style::Emoji | Style::Ascii => "!",
+            Style::Words => locale.negation(),
+        }
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "emoji" => Ok(Style::Emoji),
+            "ascii" => Ok(Style::Ascii),
+            "words" => Ok(Style::Words),
+            _ => Err("Style must be one of emoji, ascii or words"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[test]
+fn style_name() {
+    assert_eq!(Style::Emoji.name("🔷 < 🟨", Locale::En), "🔷 < 🟨");
+    assert_eq!(Style::Ascii.name("🔷 < 🟨", Locale::En), "B < Y");
+    assert_eq!(
+        Style::Words.name("🟣 smallest", Locale::En),
+        "purple smallest"
+    );
+    assert_eq!(
+        Style::Words.name("🟣 smallest", Locale::Fr),
+        "violet smallest"
+    );
+    assert!(
+        (1..=48)
+            .filter_map(crate::solver::constraints_for_card)
+            .flatten()
+            .all(|c| Style::Ascii.name(&c.name, Locale::En).is_ascii())
+    );
+}
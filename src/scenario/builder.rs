@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
+use crate::{
+    catalogue::Catalogue,
+    scenario::{
+        constraint::{Constraint, ConstraintID},
+        domain::Domain,
+    },
+    solver::{Solution, turing_solve_in},
+};
+
+/// Which constraints of a verifier card may be the real one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Selection {
+    /// Any constraint of the card.
+    All,
+    /// One of the listed constraints.
+    Only(Vec<u8>),
+    /// Any constraint but the listed ones.
+    Except(Vec<u8>),
+}
+
+/// A verifier card along with the constraints it is known to be one of.
+///
+/// Written `40` for the whole card, `40.3` or `40.0,3,6` to pin the card
+/// to some constraints and `^40.3` or `^40.0,3` to exclude some.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VerifierSpec {
+    pub card: u8,
+    pub selection: Selection,
+}
+
+impl std::str::FromStr for VerifierSpec {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (excluded, s) = match s.strip_prefix('^') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let (card, indices) = match s.split_once('.') {
+            Some((card, indices)) => (card, Some(indices)),
+            None => (s, None),
+        };
+        let card = card.parse().map_err(|_| "Invalid card number")?;
+        let Some(indices) = indices else {
+            if excluded {
+                return Err("Excluding a card needs constraint numbers, e.g. ^40.3");
+            }
+            return Ok(VerifierSpec {
+                card,
+                selection: Selection::All,
+            });
+        };
+        let indices = indices
+            .split(',')
+            .map(|idx| idx.parse().map_err(|_| "Invalid constraint number"))
+            .collect::<Result<Vec<u8>, _>>()?;
+        Ok(VerifierSpec {
+            card,
+            selection: match excluded {
+                true => Selection::Except(indices),
+                false => Selection::Only(indices),
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for VerifierSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.selection {
+            Selection::All => write!(f, "{}", self.card),
+            Selection::Only(indices) => write!(f, "{}.{}", self.card, indices.iter().join(",")),
+            Selection::Except(indices) => write!(f, "^{}.{}", self.card, indices.iter().join(",")),
+        }
+    }
+}
+
+/// The rules a game is played with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Classic,
+    Extreme,
+    Nightmare,
+}
+
+impl GameMode {
+    /// How many verifiers a game of this mode may have.
+    pub fn verifier_limits(self) -> std::ops::RangeInclusive<usize> {
+        match self {
+            GameMode::Classic => 4..=6,
+            GameMode::Extreme | GameMode::Nightmare => 1..=6,
+        }
+    }
+}
+
+/// How many verifiers a game may have: one to six,
+/// narrowed down by the game mode when one is given.
+pub fn verifier_limits(mode: Option<GameMode>) -> std::ops::RangeInclusive<usize> {
+    mode.map_or(1..=6, GameMode::verifier_limits)
+}
+
+impl std::str::FromStr for GameMode {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "classic" => Ok(GameMode::Classic),
+            "extreme" => Ok(GameMode::Extreme),
+            "nightmare" => Ok(GameMode::Nightmare),
+            _ => Err("Mode must be one of classic, extreme or nightmare"),
+        }
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GameMode::Classic => "classic",
+            GameMode::Extreme => "extreme",
+            GameMode::Nightmare => "nightmare",
+        })
+    }
+}
+
+/// Why a scenario cannot be built.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    UnknownCard(u8),
+    UnknownConstraint(ConstraintID),
+    /// The constraint is both pinned and excluded.
+    Conflict(ConstraintID),
+    /// Every constraint of the card is excluded.
+    NothingLeft(u8),
+    /// The game mode, or any game without one, does not
+    /// allow this many verifiers.
+    VerifierCount {
+        mode: Option<GameMode>,
+        count: usize,
+    },
+}
+
+impl std::fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScenarioError::UnknownCard(card) => write!(f, "There is no card {card}"),
+            ScenarioError::UnknownConstraint(id) => write!(f, "There is no constraint {id}"),
+            ScenarioError::Conflict(id) => write!(f, "{id} is both pinned and excluded"),
+            ScenarioError::NothingLeft(card) => {
+                write!(f, "Every constraint of card {card} is excluded")
+            }
+            ScenarioError::VerifierCount { mode, count } => {
+                let limits = verifier_limits(*mode);
+                match mode {
+                    Some(mode) => write!(f, "A {mode} game")?,
+                    None => write!(f, "A game")?,
+                }
+                write!(
+                    f,
+                    " has {} to {} verifiers, not {count}",
+                    limits.start(),
+                    limits.end()
+                )
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ScenarioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+/// What is known about one verifier card.
+#[derive(Default)]
+struct Verifier {
+    pinned: BTreeSet<u8>,
+    excluded: BTreeSet<u8>,
+}
+
+/// Collects verifier specifications into the constraints to solve for.
+///
+/// Instructions about the same card are combined regardless of their
+/// order: the card may be any of its pinned constraints, or any of its
+/// constraints if none is pinned, except the excluded ones.
+#[derive(Default)]
+pub struct ScenarioBuilder {
+    domain: Domain,
+    catalogue: Catalogue,
+    mode: Option<GameMode>,
+    verifiers: BTreeMap<u8, Verifier>,
+}
+
+impl ScenarioBuilder {
+    pub fn new() -> Self {
+        ScenarioBuilder::default()
+    }
+
+    pub fn domain(mut self, domain: Domain) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Take the verifier cards from the catalogue rather
+    /// than from the printed cards only.
+    pub fn catalogue(mut self, catalogue: Catalogue) -> Self {
+        self.catalogue = catalogue;
+        self
+    }
+
+    /// Limit the number of verifiers to what the game mode allows,
+    /// or to one to six verifiers without a mode.
+    pub fn mode(mut self, mode: Option<GameMode>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn verifier(mut self, spec: VerifierSpec) -> Self {
+        let verifier = self.verifiers.entry(spec.card).or_default();
+        match spec.selection {
+            Selection::All => {}
+            Selection::Only(indices) => verifier.pinned.extend(indices),
+            Selection::Except(indices) => verifier.excluded.extend(indices),
+        }
+        self
+    }
+
+    /// Number of distinct verifier cards, however many
+    /// instructions were given about each.
+    pub fn verifier_count(&self) -> usize {
+        self.verifiers.len()
+    }
+
+    /// The constraints each verifier may be, ordered by card.
+    pub fn constraints(&self) -> Result<Vec<Constraint>, ScenarioError> {
+        let count = self.verifier_count();
+        if !verifier_limits(self.mode).contains(&count) {
+            return Err(ScenarioError::VerifierCount {
+                mode: self.mode,
+                count,
+            });
+        }
+        let mut constraints = vec![];
+        for (&card, verifier) in &self.verifiers {
+            let all = self
+                .catalogue
+                .constraints(card)
+                .ok_or(ScenarioError::UnknownCard(card))?;
+            let id = |idx| ConstraintID { card, idx };
+            if let Some(&idx) = verifier
+                .pinned
+                .iter()
+                .chain(&verifier.excluded)
+                .find(|&&idx| idx as usize >= all.len())
+            {
+                return Err(ScenarioError::UnknownConstraint(id(idx)));
+            }
+            if let Some(&idx) = verifier.pinned.intersection(&verifier.excluded).next() {
+                return Err(ScenarioError::Conflict(id(idx)));
+            }
+            let before = constraints.len();
+            constraints.extend(all.into_iter().filter(|c| {
+                (verifier.pinned.is_empty() || verifier.pinned.contains(&c.id.idx))
+                    && !verifier.excluded.contains(&c.id.idx)
+            }));
+            if constraints.len() == before {
+                return Err(ScenarioError::NothingLeft(card));
+            }
+        }
+        Ok(constraints)
+    }
+
+    pub fn solve(&self) -> Result<Vec<Solution>, ScenarioError> {
+        Ok(turing_solve_in(self.domain, self.constraints()?))
+    }
+}
+
+#[test]
+fn verifier_spec_round_trip() {
+    for spec in ["40", "40.3", "40.0,3,6", "^40.3", "^40.0,3"] {
+        assert_eq!(spec.parse::<VerifierSpec>().unwrap().to_string(), spec);
+    }
+    assert!("^40".parse::<VerifierSpec>().is_err());
+    assert!("40.a".parse::<VerifierSpec>().is_err());
+}
+
+#[test]
+fn builder_combines_instructions_on_a_card() {
+    let build = |specs: &[&str]| {
+        specs
+            .iter()
+            .fold(ScenarioBuilder::new(), |builder, spec| {
+                builder.verifier(spec.parse().unwrap())
+            })
+            .constraints()
+            .map(|constraints| constraints.iter().map(|c| c.id.to_string()).join(" "))
+    };
+    assert_eq!(
+        build(&["40.0,3,6", "10.1"]),
+        Ok("(10.1) (40.0) (40.3) (40.6)".into())
+    );
+    assert_eq!(build(&["^40.3", "40.0,3,6"]), build(&["40.0,3,6", "^40.3"]));
+    assert_eq!(
+        build(&["40.0,3,6", "^40.3"]),
+        Err(ScenarioError::Conflict(ConstraintID { card: 40, idx: 3 }))
+    );
+    assert_eq!(build(&["3", "^3.2"]), Ok("(3.0) (3.1)".into()));
+    assert_eq!(build(&["^3.0,1,2"]), Err(ScenarioError::NothingLeft(3)));
+    assert_eq!(
+        build(&["3.7"]),
+        Err(ScenarioError::UnknownConstraint(ConstraintID {
+            card: 3,
+            idx: 7
+        }))
+    );
+    assert_eq!(build(&["32"]), Err(ScenarioError::UnknownCard(32)));
+}
+
+#[test]
+fn builder_counts_distinct_verifiers() {
+    let build = |mode, specs: &str| {
+        specs
+            .split(' ')
+            .fold(ScenarioBuilder::new().mode(Some(mode)), |builder, spec| {
+                builder.verifier(spec.parse().unwrap())
+            })
+            .constraints()
+            .map(|_| ())
+    };
+    let specs = "3 ^3.0 ^3.1 10 14 17 18 19";
+    assert_eq!(build(GameMode::Classic, specs), Ok(()));
+    assert_eq!(
+        build(GameMode::Classic, "3 10 14"),
+        Err(ScenarioError::VerifierCount {
+            mode: Some(GameMode::Classic),
+            count: 3
+        })
+    );
+    assert_eq!(build(GameMode::Nightmare, "3 10 14"), Ok(()));
+    assert!(build(GameMode::Extreme, &format!("{specs} 20")).is_err());
+    let without_mode = |specs: &str| {
+        specs
+            .split(' ')
+            .fold(ScenarioBuilder::new(), |builder, spec| {
+                builder.verifier(spec.parse().unwrap())
+            })
+            .constraints()
+            .map(|_| ())
+    };
+    assert_eq!(without_mode("3 10 14"), Ok(()));
+    assert_eq!(without_mode(specs), Ok(()));
+    assert_eq!(
+        without_mode(&format!("{specs} 20")),
+        Err(ScenarioError::VerifierCount {
+            mode: None,
+            count: 7
+        })
+    );
+    assert_eq!(
+        ScenarioError::VerifierCount {
+            mode: None,
+            count: 7
+        }
+        .to_string(),
+        "A game has 1 to 6 verifiers, not 7"
+    );
+}
@@ -1,32 +1,72 @@
+use std::sync::Arc;
+
 use itertools::Itertools;
 
 use crate::scenario::{
-    code::Code,
     constraint::{Constraint, ConstraintID},
+    rule::{Expr, Expr::*, Op::*, Pattern, Rule, Rule::*},
 };
 
+const B: Expr = Digit(0);
+const Y: Expr = Digit(1);
+const P: Expr = Digit(2);
+
+/// Three digits counting up or down by one.
+fn sequence() -> Rule {
+    Or([All(Pattern::Difference(1)), All(Pattern::Difference(-1))].into())
+}
+
 #[derive(Clone, Debug)]
 pub struct Card {
+    pub(crate) num: u8,
     pub(crate) constraints: Vec<Constraint>,
+    /// Whether at most one constraint of this card can hold for any code.
+    pub(crate) exclusive: bool,
 }
 
 impl Card {
     /// Convenience initializer.
-    fn new<const N: usize>(num: u8, constraints: [(&'static str, fn(&Code) -> bool); N]) -> Self {
+    fn new(num: u8, constraints: impl IntoIterator<Item = (impl Into<Arc<str>>, Rule)>) -> Self {
         Card {
+            num,
             constraints: constraints
-                .iter()
+                .into_iter()
                 .enumerate()
-                .map(|(idx, (name, verifier))| Constraint {
+                .map(|(idx, (name, rule))| Constraint {
                     id: ConstraintID {
                         card: num,
                         idx: idx as u8,
                     },
-                    name: *name,
-                    verifier: *verifier,
+                    name: name.into(),
+                    rule,
+                    custom: false,
                 })
                 .collect_vec(),
+            exclusive: false,
+        }
+    }
+
+    /// Initializer for cards asking about a single property of the code,
+    /// so that at most one of their constraints holds.
+    fn exclusive(num: u8, constraints: impl IntoIterator<Item = (&'static str, Rule)>) -> Self {
+        Card {
+            exclusive: true,
+            ..Card::new(num, constraints)
+        }
+    }
+
+    /// A card read from a card file rather than printed in the game.
+    pub(crate) fn custom(num: u8, constraints: Vec<(String, Rule)>, exclusive: bool) -> Self {
+        let mut card = Card::new(num, constraints);
+        for constraint in &mut card.constraints {
+            constraint.custom = true;
         }
+        Card { exclusive, ..card }
+    }
+
+    /// Every card printed in the game, by number.
+    pub(crate) fn all() -> impl Iterator<Item = Card> {
+        (1..=u8::MAX).filter_map(|num| Card::try_from(num).ok())
     }
 }
 
@@ -35,578 +75,525 @@ impl TryFrom<u8> for Card {
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            1 => Ok(Card::new(
+            1 => Ok(Card::exclusive(
                 1,
                 [
-                    ("🔷 = 1", |code| code.blue() == 1),
-                    ("🔷 > 1", |code| code.blue() > 1),
+                    ("🔷 = 1", Compare(B, Eq, Value(1))),
+                    ("🔷 > 1", Compare(B, Gt, Value(1))),
                 ],
             )),
-            2 => Ok(Card::new(
+            2 => Ok(Card::exclusive(
                 2,
                 [
-                    ("🔷 < 3", |code| code.blue() < 3),
-                    ("🔷 = 3", |code| code.blue() == 3),
-                    ("🔷 > 3", |code| code.blue() > 3),
+                    ("🔷 < 3", Compare(B, Lt, Value(3))),
+                    ("🔷 = 3", Compare(B, Eq, Value(3))),
+                    ("🔷 > 3", Compare(B, Gt, Value(3))),
                 ],
             )),
-            3 => Ok(Card::new(
+            3 => Ok(Card::exclusive(
                 3,
                 [
-                    ("🟨 < 3", |code| code.yellow() < 3),
-                    ("🟨 = 3", |code| code.yellow() == 3),
-                    ("🟨 > 3", |code| code.yellow() > 3),
+                    ("🟨 < 3", Compare(Y, Lt, Value(3))),
+                    ("🟨 = 3", Compare(Y, Eq, Value(3))),
+                    ("🟨 > 3", Compare(Y, Gt, Value(3))),
                 ],
             )),
-            4 => Ok(Card::new(
+            4 => Ok(Card::exclusive(
                 4,
                 [
-                    ("🟨 < 4", |code| code.yellow() < 4),
-                    ("🟨 = 4", |code| code.yellow() == 4),
-                    ("🟨 > 4", |code| code.yellow() > 4),
+                    ("🟨 < 4", Compare(Y, Lt, Value(4))),
+                    ("🟨 = 4", Compare(Y, Eq, Value(4))),
+                    ("🟨 > 4", Compare(Y, Gt, Value(4))),
                 ],
             )),
-            5 => Ok(Card::new(
+            5 => Ok(Card::exclusive(
                 5,
-                [
-                    ("🔷 is even", |code| code.blue() % 2 == 0),
-                    ("🔷 is odd", |code| code.blue() % 2 != 0),
-                ],
+                [("🔷 is even", Even(B)), ("🔷 is odd", Odd(B))],
             )),
-            6 => Ok(Card::new(
+            6 => Ok(Card::exclusive(
                 6,
-                [
-                    ("🟨 is even", |code| code.yellow() % 2 == 0),
-                    ("🟨 is odd", |code| code.yellow() % 2 != 0),
-                ],
+                [("🟨 is even", Even(Y)), ("🟨 is odd", Odd(Y))],
             )),
-            7 => Ok(Card::new(
+            7 => Ok(Card::exclusive(
                 7,
-                [
-                    ("🟣 is even", |code| code.purple() % 2 == 0),
-                    ("🟣 is odd", |code| code.purple() % 2 != 0),
-                ],
+                [("🟣 is even", Even(P)), ("🟣 is odd", Odd(P))],
             )),
-            8 => Ok(Card::new(
+            8 => Ok(Card::exclusive(
                 8,
                 [
-                    ("No 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 0
-                    }),
-                    ("One 1", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 1
-                    }),
-                    ("Two 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 2
-                    }),
-                    ("Three 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 3
-                    }),
-                ],
-            )),
-            9 => Ok(Card::new(
+                    ("No 1s", Compare(Count(Pattern::Value(1)), Eq, Value(0))),
+                    ("One 1", Compare(Count(Pattern::Value(1)), Eq, Value(1))),
+                    ("Two 1s", Compare(Count(Pattern::Value(1)), Eq, Value(2))),
+                    ("Three 1s", Compare(Count(Pattern::Value(1)), Eq, Value(3))),
+                ],
+            )),
+            9 => Ok(Card::exclusive(
                 9,
                 [
-                    ("No 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 0
-                    }),
-                    ("One 3", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 1
-                    }),
-                    ("Two 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 2
-                    }),
-                    ("Three 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 3
-                    }),
-                ],
-            )),
-            10 => Ok(Card::new(
+                    ("No 3s", Compare(Count(Pattern::Value(3)), Eq, Value(0))),
+                    ("One 3", Compare(Count(Pattern::Value(3)), Eq, Value(1))),
+                    ("Two 3s", Compare(Count(Pattern::Value(3)), Eq, Value(2))),
+                    ("Three 3s", Compare(Count(Pattern::Value(3)), Eq, Value(3))),
+                ],
+            )),
+            10 => Ok(Card::exclusive(
                 10,
                 [
-                    ("No 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 0
-                    }),
-                    ("One 4", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 1
-                    }),
-                    ("Two 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 2
-                    }),
-                    ("Three 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 3
-                    }),
-                ],
-            )),
-            11 => Ok(Card::new(
+                    ("No 4s", Compare(Count(Pattern::Value(4)), Eq, Value(0))),
+                    ("One 4", Compare(Count(Pattern::Value(4)), Eq, Value(1))),
+                    ("Two 4s", Compare(Count(Pattern::Value(4)), Eq, Value(2))),
+                    ("Three 4s", Compare(Count(Pattern::Value(4)), Eq, Value(3))),
+                ],
+            )),
+            11 => Ok(Card::exclusive(
                 11,
                 [
-                    ("🔷 < 🟨", |code| code.blue() < code.yellow()),
-                    ("🔷 = 🟨", |code| code.blue() == code.yellow()),
-                    ("🔷 > 🟨", |code| code.blue() > code.yellow()),
+                    ("🔷 < 🟨", Compare(B, Lt, Y)),
+                    ("🔷 = 🟨", Compare(B, Eq, Y)),
+                    ("🔷 > 🟨", Compare(B, Gt, Y)),
                 ],
             )),
-            12 => Ok(Card::new(
+            12 => Ok(Card::exclusive(
                 12,
                 [
-                    ("🔷 < 🟣", |code| code.blue() < code.purple()),
-                    ("🔷 = 🟣", |code| code.blue() == code.purple()),
-                    ("🔷 > 🟣", |code: &Code| code.blue() > code.purple()),
+                    ("🔷 < 🟣", Compare(B, Lt, P)),
+                    ("🔷 = 🟣", Compare(B, Eq, P)),
+                    ("🔷 > 🟣", Compare(B, Gt, P)),
                 ],
             )),
-            13 => Ok(Card::new(
+            13 => Ok(Card::exclusive(
                 13,
                 [
-                    ("🟨 < 🟣", |code| code.yellow() < code.purple()),
-                    ("🟨 = 🟣", |code| code.yellow() == code.purple()),
-                    ("🟨 > 🟣", |code| code.yellow() > code.purple()),
+                    ("🟨 < 🟣", Compare(Y, Lt, P)),
+                    ("🟨 = 🟣", Compare(Y, Eq, P)),
+                    ("🟨 > 🟣", Compare(Y, Gt, P)),
                 ],
             )),
-            14 => Ok(Card::new(
+            14 => Ok(Card::exclusive(
                 14,
                 [
-                    ("🔷 smallest", |code| {
-                        code.blue() < code.yellow() && code.blue() < code.purple()
-                    }),
-                    ("🟨 smallest", |code| {
-                        code.yellow() < code.blue() && code.yellow() < code.purple()
-                    }),
-                    ("🟣 smallest", |code| {
-                        code.purple() < code.yellow() && code.purple() < code.blue()
-                    }),
-                ],
-            )),
-            15 => Ok(Card::new(
+                    (
+                        "🔷 smallest",
+                        And([Compare(B, Lt, Y), Compare(B, Lt, P)].into()),
+                    ),
+                    (
+                        "🟨 smallest",
+                        And([Compare(Y, Lt, B), Compare(Y, Lt, P)].into()),
+                    ),
+                    (
+                        "🟣 smallest",
+                        And([Compare(P, Lt, Y), Compare(P, Lt, B)].into()),
+                    ),
+                ],
+            )),
+            15 => Ok(Card::exclusive(
                 15,
                 [
-                    ("🔷 is largest", |code| {
-                        code.blue() > code.yellow() && code.blue() > code.purple()
-                    }),
-                    ("🟨 is largest", |code| {
-                        code.yellow() > code.blue() && code.yellow() > code.purple()
-                    }),
-                    ("🟣 is largest", |code| {
-                        code.purple() > code.yellow() && code.purple() > code.blue()
-                    }),
-                ],
-            )),
-            16 => Ok(Card::new(
+                    (
+                        "🔷 is largest",
+                        And([Compare(B, Gt, Y), Compare(B, Gt, P)].into()),
+                    ),
+                    (
+                        "🟨 is largest",
+                        And([Compare(Y, Gt, B), Compare(Y, Gt, P)].into()),
+                    ),
+                    (
+                        "🟣 is largest",
+                        And([Compare(P, Gt, Y), Compare(P, Gt, B)].into()),
+                    ),
+                ],
+            )),
+            16 => Ok(Card::exclusive(
                 16,
                 [
-                    ("Even > Odd", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 == 0).count() == 2
-                    }),
-                    ("Odd > Even", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 != 0).count() == 2
-                    }),
+                    (
+                        "Even > Odd",
+                        Compare(Count(Pattern::Even), Gt, Count(Pattern::Odd)),
+                    ),
+                    (
+                        "Odd > Even",
+                        Compare(Count(Pattern::Odd), Gt, Count(Pattern::Even)),
+                    ),
                 ],
             )),
-            17 => Ok(Card::new(
+            17 => Ok(Card::exclusive(
                 17,
                 [
-                    ("No even #", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 == 0).count() == 0
-                    }),
-                    ("1 even #", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 == 0).count() == 1
-                    }),
-                    ("2 even #s", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 == 0).count() == 2
-                    }),
-                    ("3 even #s", |code| {
-                        code.iter().filter(|n: &u8| *n % 2 == 0).count() == 3
-                    }),
-                ],
-            )),
-            18 => Ok(Card::new(
+                    ("No even #", Compare(Count(Pattern::Even), Eq, Value(0))),
+                    ("1 even #", Compare(Count(Pattern::Even), Eq, Value(1))),
+                    ("2 even #s", Compare(Count(Pattern::Even), Eq, Value(2))),
+                    ("3 even #s", Compare(Count(Pattern::Even), Eq, Value(3))),
+                ],
+            )),
+            18 => Ok(Card::exclusive(
                 18,
+                [("Sum even", Even(Total)), ("Sum odd", Odd(Total))],
+            )),
+            19 => Ok(Card::exclusive(
+                19,
                 [
-                    ("Sum even", |code| {
-                        code.iter().map(|n: u8| n).sum::<u8>() % 2 == 0
-                    }),
-                    ("Sum odd", |code| {
-                        code.iter().map(|n: u8| n).sum::<u8>() % 2 != 0
-                    }),
+                    ("🔷 + 🟨 < 6", Compare(Sum(&[0, 1]), Lt, Value(6))),
+                    ("🔷 + 🟨 = 6", Compare(Sum(&[0, 1]), Eq, Value(6))),
+                    ("🔷 + 🟨 > 6", Compare(Sum(&[0, 1]), Gt, Value(6))),
                 ],
             )),
-            19 => Ok(Card::new(
-                19,
+            20 => Ok(Card::exclusive(
+                20,
                 [
-                    ("🔷 + 🟨 < 6", |code| code.blue() + code.yellow() < 6),
-                    ("🔷 + 🟨 = 6", |code| code.blue() + code.yellow() == 6),
-                    ("🔷 + 🟨 > 6", |code| code.blue() + code.yellow() > 6),
-                ],
-            )),
-            20 => {
-                Ok(Card::new(
-                    20,
-                    [
-                        ("Triple #", |code| {
-                            code.blue() == code.yellow() && code.yellow() == code.purple()
-                        }),
-                        ("Double #", |code| {
-                            (code.blue() == code.yellow() && code.yellow() != code.purple()) // Only blue and yellow
-                        || (code.yellow() == code.purple() && code.blue() != code.yellow()) // Only yellow and purple
-                        || (code.blue() == code.purple() && code.blue() != code.yellow()) // Only blue and purple
-                        }),
-                        ("No repetition", |code| {
-                            code.blue() != code.yellow()
-                                && code.yellow() != code.purple()
-                                && code.blue() != code.purple()
-                        }),
-                    ],
-                ))
-            }
-            21 => Ok(Card::new(
+                    ("Triple #", Compare(Repeats, Ge, Value(3))),
+                    ("Double #", Compare(Repeats, Eq, Value(2))),
+                    ("No repetition", Compare(Repeats, Eq, Value(1))),
+                ],
+            )),
+            21 => Ok(Card::exclusive(
                 21,
                 [
-                    ("A pairs", |code| {
-                        code.iter()
-                            .tuple_combinations()
-                            .filter(|(a, b)| a == b)
-                            .count()
-                            == 1
-                    }),
-                    ("No pairs", |code| {
-                        code.iter().tuple_combinations().all(|(a, b)| a != b)
-                    }),
+                    ("A pair", Compare(Count(Pattern::Pair(Eq)), Eq, Value(1))),
+                    ("No pairs", All(Pattern::Pair(Ne))),
                 ],
             )),
-            22 => Ok(Card::new(
+            22 => Ok(Card::exclusive(
                 22,
                 [
-                    ("Ascending", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| a < b)
-                    }),
-                    ("Descending", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| a > b)
-                    }),
-                    ("No order", |code| {
-                        !code.iter().tuple_windows().all(|(a, b)| a < b)
-                            && !code.iter().tuple_windows().all(|(a, b)| a > b)
-                    }),
+                    ("Ascending", All(Pattern::Step(Lt))),
+                    ("Descending", All(Pattern::Step(Gt))),
+                    (
+                        "No order",
+                        Not(Or([All(Pattern::Step(Lt)), All(Pattern::Step(Gt))].into()).into()),
+                    ),
                 ],
             )),
-            23 => Ok(Card::new(
+            23 => Ok(Card::exclusive(
                 23,
                 [
-                    ("Sum < 6", |code| code.iter().sum::<u8>() < 6),
-                    ("Sum = 6", |code| code.iter().sum::<u8>() == 6),
-                    ("Sum > 6", |code| code.iter().sum::<u8>() > 6),
+                    ("Sum < 6", Compare(Total, Lt, Value(6))),
+                    ("Sum = 6", Compare(Total, Eq, Value(6))),
+                    ("Sum > 6", Compare(Total, Gt, Value(6))),
                 ],
             )),
-            24 => Ok(Card::new(
+            24 => Ok(Card::exclusive(
                 24,
                 [
-                    ("Ascending", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| a < b)
-                    }),
-                    ("2 ascending", |code| {
-                        code.iter().tuple_windows().filter(|(a, b)| a < b).count() == 1
-                    }),
-                    ("None ascending", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| !(a < b))
-                    }),
-                ],
-            )),
-            25 => Ok(Card::new(
+                    ("3 in asc sequence", All(Pattern::Difference(1))),
+                    (
+                        "2 in asc sequence",
+                        And([
+                            Not(All(Pattern::Difference(1)).into()),
+                            Compare(Count(Pattern::Difference(1)), Ge, Value(1)),
+                        ]
+                        .into()),
+                    ),
+                    (
+                        "No asc sequence",
+                        Compare(Count(Pattern::Difference(1)), Eq, Value(0)),
+                    ),
+                ],
+            )),
+            25 => Ok(Card::exclusive(
                 25,
                 [
-                    ("No sequence asc/dsc", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| a != b)
-                    }),
-                    ("2 asc/dsc", |code| {
-                        code.iter().tuple_windows().filter(|(a, b)| a != b).count() == 1
-                    }),
-                    ("3 asc/dsc", |code| {
-                        code.iter().tuple_windows().all(|(a, b)| a < b)
-                            || code.iter().tuple_windows().all(|(a, b)| a > b)
-                    }),
+                    (
+                        "No asc/dsc sequence",
+                        And([
+                            Compare(Count(Pattern::Difference(1)), Eq, Value(0)),
+                            Compare(Count(Pattern::Difference(-1)), Eq, Value(0)),
+                        ]
+                        .into()),
+                    ),
+                    (
+                        "2 in asc/dsc sequence",
+                        And([
+                            Not(sequence().into()),
+                            Or([
+                                Compare(Count(Pattern::Difference(1)), Ge, Value(1)),
+                                Compare(Count(Pattern::Difference(-1)), Ge, Value(1)),
+                            ]
+                            .into()),
+                        ]
+                        .into()),
+                    ),
+                    ("3 in asc/dsc sequence", sequence()),
                 ],
             )),
             26 => Ok(Card::new(
                 26,
                 [
-                    ("🔷 < 3", |code| code.blue() < 3),
-                    ("🟨 < 3", |code| code.yellow() < 3),
-                    ("🟣 < 3", |code| code.purple() < 3),
+                    ("🔷 < 3", Compare(B, Lt, Value(3))),
+                    ("🟨 < 3", Compare(Y, Lt, Value(3))),
+                    ("🟣 < 3", Compare(P, Lt, Value(3))),
                 ],
             )),
             27 => Ok(Card::new(
                 27,
                 [
-                    ("🔷 < 4", |code| code.blue() < 4),
-                    ("🟨 < 4", |code| code.yellow() < 4),
-                    ("🟣 < 4", |code| code.purple() < 4),
+                    ("🔷 < 4", Compare(B, Lt, Value(4))),
+                    ("🟨 < 4", Compare(Y, Lt, Value(4))),
+                    ("🟣 < 4", Compare(P, Lt, Value(4))),
                 ],
             )),
             28 => Ok(Card::new(
                 28,
                 [
-                    ("🔷 = 1", |code| code.blue() == 1),
-                    ("🟨 = 1", |code| code.yellow() == 1),
-                    ("🟣 = 1", |code| code.purple() == 1),
+                    ("🔷 = 1", Compare(B, Eq, Value(1))),
+                    ("🟨 = 1", Compare(Y, Eq, Value(1))),
+                    ("🟣 = 1", Compare(P, Eq, Value(1))),
                 ],
             )),
             29 => Ok(Card::new(
                 29,
                 [
-                    ("🔷 = 1", |code| code.blue() == 3),
-                    ("🟨 = 1", |code| code.yellow() == 3),
-                    ("🟣 = 1", |code| code.purple() == 3),
+                    ("🔷 = 3", Compare(B, Eq, Value(3))),
+                    ("🟨 = 3", Compare(Y, Eq, Value(3))),
+                    ("🟣 = 3", Compare(P, Eq, Value(3))),
                 ],
             )),
             30 => Ok(Card::new(
                 30,
                 [
-                    ("🔷 = 4", |code| code.blue() == 4),
-                    ("🟨 = 4", |code| code.yellow() == 4),
-                    ("🟣 = 4", |code| code.purple() == 4),
+                    ("🔷 = 4", Compare(B, Eq, Value(4))),
+                    ("🟨 = 4", Compare(Y, Eq, Value(4))),
+                    ("🟣 = 4", Compare(P, Eq, Value(4))),
                 ],
             )),
             31 => Ok(Card::new(
                 31,
                 [
-                    ("🔷 > 1", |code| code.blue() > 1),
-                    ("🟨 > 1", |code| code.yellow() > 1),
-                    ("🟣 > 1", |code| code.purple() > 1),
+                    ("🔷 > 1", Compare(B, Gt, Value(1))),
+                    ("🟨 > 1", Compare(Y, Gt, Value(1))),
+                    ("🟣 > 1", Compare(P, Gt, Value(1))),
                 ],
             )),
             33 => Ok(Card::new(
                 33,
                 [
-                    ("🔷 is even", |code| code.blue() % 2 == 0),
-                    ("🟨 is even", |code| code.yellow() % 2 == 0),
-                    ("🟣 is even", |code| code.purple() % 2 == 0),
-                    ("🔷 is odd", |code| code.blue() % 2 != 0),
-                    ("🟨 is odd", |code| code.yellow() % 2 != 0),
-                    ("🟣 is odd", |code| code.purple() % 2 != 0),
+                    ("🔷 is even", Even(B)),
+                    ("🟨 is even", Even(Y)),
+                    ("🟣 is even", Even(P)),
+                    ("🔷 is odd", Odd(B)),
+                    ("🟨 is odd", Odd(Y)),
+                    ("🟣 is odd", Odd(P)),
                 ],
             )),
             34 => Ok(Card::new(
                 34,
                 [
-                    ("🔷 <= 🟨 & 🟣", |code| {
-                        code.blue() <= code.yellow() && code.blue() <= code.purple()
-                    }),
-                    ("🟣 <= 🔷 &🟨", |code| {
-                        code.yellow() <= code.blue() && code.yellow() <= code.purple()
-                    }),
-                    ("🟨 <= 🔷 & 🟣", |code| {
-                        code.purple() <= code.blue() && code.purple() <= code.yellow()
-                    }),
+                    (
+                        "🔷 <= 🟨 & 🟣",
+                        And([Compare(B, Le, Y), Compare(B, Le, P)].into()),
+                    ),
+                    (
+                        "🟨 <= 🔷 & 🟣",
+                        And([Compare(Y, Le, B), Compare(Y, Le, P)].into()),
+                    ),
+                    (
+                        "🟣 <= 🔷 & 🟨",
+                        And([Compare(P, Le, B), Compare(P, Le, Y)].into()),
+                    ),
                 ],
             )),
             35 => Ok(Card::new(
                 35,
                 [
-                    ("🔷 >= 🟨 & 🟣", |code| {
-                        code.blue() >= code.yellow() && code.blue() >= code.purple()
-                    }),
-                    ("🟣 >= 🔷 &🟨", |code| {
-                        code.yellow() >= code.blue() && code.yellow() >= code.purple()
-                    }),
-                    ("🟨 >= 🔷 & 🟣", |code| {
-                        code.purple() >= code.blue() && code.purple() >= code.yellow()
-                    }),
+                    (
+                        "🔷 >= 🟨 & 🟣",
+                        And([Compare(B, Ge, Y), Compare(B, Ge, P)].into()),
+                    ),
+                    (
+                        "🟨 >= 🔷 & 🟣",
+                        And([Compare(Y, Ge, B), Compare(Y, Ge, P)].into()),
+                    ),
+                    (
+                        "🟣 >= 🔷 & 🟨",
+                        And([Compare(P, Ge, B), Compare(P, Ge, Y)].into()),
+                    ),
                 ],
             )),
             36 => Ok(Card::new(
                 36,
                 [
-                    ("Sum mutiple of 3", |code| code.iter().sum::<u8>() % 3 == 0),
-                    ("Sum mutiple of 4", |code| code.iter().sum::<u8>() % 4 == 0),
-                    ("Sum mutiple of 5", |code| code.iter().sum::<u8>() % 5 == 0),
+                    ("Sum multiple of 3", MultipleOf(Total, 3)),
+                    ("Sum multiple of 4", MultipleOf(Total, 4)),
+                    ("Sum multiple of 5", MultipleOf(Total, 5)),
                 ],
             )),
             37 => Ok(Card::new(
                 37,
                 [
-                    ("🔷 + 🟨 = 4", |code| code.blue() + code.yellow() == 4),
-                    ("🔷 + 🟣 = 4", |code| code.blue() + code.purple() == 4),
-                    ("🟨 + 🟣 = 4", |code| {
-                        code.yellow() + code.purple() == 4
-                    }),
+                    ("🔷 + 🟨 = 4", Compare(Sum(&[0, 1]), Eq, Value(4))),
+                    ("🔷 + 🟣 = 4", Compare(Sum(&[0, 2]), Eq, Value(4))),
+                    ("🟨 + 🟣 = 4", Compare(Sum(&[1, 2]), Eq, Value(4))),
                 ],
             )),
             38 => Ok(Card::new(
                 38,
                 [
-                    ("🔷 + 🟨 = 6", |code| code.blue() + code.yellow() == 6),
-                    ("🔷 + 🟣 = 6", |code| code.blue() + code.purple() == 6),
-                    ("🟨 + 🟣 = 6", |code| {
-                        code.yellow() + code.purple() == 6
-                    }),
+                    ("🔷 + 🟨 = 6", Compare(Sum(&[0, 1]), Eq, Value(6))),
+                    ("🔷 + 🟣 = 6", Compare(Sum(&[0, 2]), Eq, Value(6))),
+                    ("🟨 + 🟣 = 6", Compare(Sum(&[1, 2]), Eq, Value(6))),
                 ],
             )),
             39 => Ok(Card::new(
                 39,
                 [
-                    ("🔷 = 1", |code| code.blue() == 1),
-                    ("🔷 > 1", |code| code.blue() > 1),
-                    ("🟨 = 1", |code| code.yellow() == 1),
-                    ("🟨 > 1", |code| code.yellow() > 1),
-                    ("🟣 = 1", |code| code.purple() == 1),
-                    ("🟣 > 1", |code| code.purple() > 1),
+                    ("🔷 = 1", Compare(B, Eq, Value(1))),
+                    ("🔷 > 1", Compare(B, Gt, Value(1))),
+                    ("🟨 = 1", Compare(Y, Eq, Value(1))),
+                    ("🟨 > 1", Compare(Y, Gt, Value(1))),
+                    ("🟣 = 1", Compare(P, Eq, Value(1))),
+                    ("🟣 > 1", Compare(P, Gt, Value(1))),
                 ],
             )),
             40 => Ok(Card::new(
                 40,
                 [
-                    ("🔷 < 3", |code| code.blue() < 3),
-                    ("🔷 = 3", |code| code.blue() == 3),
-                    ("🔷 > 3", |code| code.blue() > 3),
-                    ("🟨 < 3", |code| code.yellow() < 3),
-                    ("🟨 = 3", |code| code.yellow() == 3),
-                    ("🟨 > 3", |code| code.yellow() > 3),
-                    ("🟣 < 3", |code| code.purple() < 3),
-                    ("🟣 = 3", |code| code.purple() == 3),
-                    ("🟣 > 3", |code| code.purple() > 3),
+                    ("🔷 < 3", Compare(B, Lt, Value(3))),
+                    ("🔷 = 3", Compare(B, Eq, Value(3))),
+                    ("🔷 > 3", Compare(B, Gt, Value(3))),
+                    ("🟨 < 3", Compare(Y, Lt, Value(3))),
+                    ("🟨 = 3", Compare(Y, Eq, Value(3))),
+                    ("🟨 > 3", Compare(Y, Gt, Value(3))),
+                    ("🟣 < 3", Compare(P, Lt, Value(3))),
+                    ("🟣 = 3", Compare(P, Eq, Value(3))),
+                    ("🟣 > 3", Compare(P, Gt, Value(3))),
                 ],
             )),
             41 => Ok(Card::new(
                 41,
                 [
-                    ("🔷 < 4", |code| code.blue() < 4),
-                    ("🔷 = 4", |code| code.blue() == 4),
-                    ("🔷 > 4", |code| code.blue() > 4),
-                    ("🟨 < 4", |code| code.yellow() < 4),
-                    ("🟨 = 4", |code| code.yellow() == 4),
-                    ("🟨 > 4", |code| code.yellow() > 4),
-                    ("🟣 < 4", |code| code.purple() < 4),
-                    ("🟣 = 4", |code| code.purple() == 4),
-                    ("🟣 > 4", |code| code.purple() > 4),
+                    ("🔷 < 4", Compare(B, Lt, Value(4))),
+                    ("🔷 = 4", Compare(B, Eq, Value(4))),
+                    ("🔷 > 4", Compare(B, Gt, Value(4))),
+                    ("🟨 < 4", Compare(Y, Lt, Value(4))),
+                    ("🟨 = 4", Compare(Y, Eq, Value(4))),
+                    ("🟨 > 4", Compare(Y, Gt, Value(4))),
+                    ("🟣 < 4", Compare(P, Lt, Value(4))),
+                    ("🟣 = 4", Compare(P, Eq, Value(4))),
+                    ("🟣 > 4", Compare(P, Gt, Value(4))),
                 ],
             )),
             42 => Ok(Card::new(
                 42,
                 [
-                    ("🔷 smallest", |code| {
-                        code.blue() < code.yellow() && code.blue() < code.purple()
-                    }),
-                    ("🟨 smallest", |code| {
-                        code.yellow() < code.blue() && code.yellow() < code.purple()
-                    }),
-                    ("🟣 smallest", |code| {
-                        code.purple() < code.yellow() && code.purple() < code.blue()
-                    }),
-                    ("🔷 largest", |code| {
-                        code.blue() > code.yellow() && code.blue() > code.purple()
-                    }),
-                    ("🟨 largest", |code| {
-                        code.yellow() > code.blue() && code.yellow() > code.purple()
-                    }),
-                    ("🟣 largest", |code| {
-                        code.purple() > code.yellow() && code.purple() > code.blue()
-                    }),
+                    (
+                        "🔷 smallest",
+                        And([Compare(B, Lt, Y), Compare(B, Lt, P)].into()),
+                    ),
+                    (
+                        "🟨 smallest",
+                        And([Compare(Y, Lt, B), Compare(Y, Lt, P)].into()),
+                    ),
+                    (
+                        "🟣 smallest",
+                        And([Compare(P, Lt, Y), Compare(P, Lt, B)].into()),
+                    ),
+                    (
+                        "🔷 largest",
+                        And([Compare(B, Gt, Y), Compare(B, Gt, P)].into()),
+                    ),
+                    (
+                        "🟨 largest",
+                        And([Compare(Y, Gt, B), Compare(Y, Gt, P)].into()),
+                    ),
+                    (
+                        "🟣 largest",
+                        And([Compare(P, Gt, Y), Compare(P, Gt, B)].into()),
+                    ),
                 ],
             )),
             43 => Ok(Card::new(
                 43,
                 [
-                    ("🔷 < 🟨", |code| code.blue() < code.yellow()),
-                    ("🔷 = 🟨", |code| code.blue() == code.yellow()),
-                    ("🔷 > 🟨", |code| code.blue() > code.yellow()),
-                    ("🔷 < 🟣", |code| code.blue() < code.purple()),
-                    ("🔷 = 🟣", |code| code.blue() == code.purple()),
-                    ("🔷 > 🟣", |code: &Code| code.blue() > code.purple()),
+                    ("🔷 < 🟨", Compare(B, Lt, Y)),
+                    ("🔷 = 🟨", Compare(B, Eq, Y)),
+                    ("🔷 > 🟨", Compare(B, Gt, Y)),
+                    ("🔷 < 🟣", Compare(B, Lt, P)),
+                    ("🔷 = 🟣", Compare(B, Eq, P)),
+                    ("🔷 > 🟣", Compare(B, Gt, P)),
                 ],
             )),
             44 => Ok(Card::new(
                 44,
                 [
-                    ("🟨 < 🔷", |code| code.yellow() < code.blue()),
-                    ("🟨 = 🔷", |code| code.yellow() == code.blue()),
-                    ("🟨 > 🔷", |code| code.yellow() > code.blue()),
-                    ("🟨 < 🟣", |code| code.yellow() < code.purple()),
-                    ("🟨 = 🟣", |code| code.yellow() == code.purple()),
-                    ("🟨 > 🟣", |code| code.yellow() > code.purple()),
+                    ("🟨 < 🔷", Compare(Y, Lt, B)),
+                    ("🟨 = 🔷", Compare(Y, Eq, B)),
+                    ("🟨 > 🔷", Compare(Y, Gt, B)),
+                    ("🟨 < 🟣", Compare(Y, Lt, P)),
+                    ("🟨 = 🟣", Compare(Y, Eq, P)),
+                    ("🟨 > 🟣", Compare(Y, Gt, P)),
                 ],
             )),
             45 => Ok(Card::new(
                 45,
                 [
-                    ("No 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 0
-                    }),
-                    ("One 1", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 1
-                    }),
-                    ("Two 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 2
-                    }),
-                    ("No 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 0
-                    }),
-                    ("One 3", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 1
-                    }),
-                    ("Two 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 2
-                    }),
+                    ("No 1s", Compare(Count(Pattern::Value(1)), Eq, Value(0))),
+                    ("One 1", Compare(Count(Pattern::Value(1)), Eq, Value(1))),
+                    ("Two 1s", Compare(Count(Pattern::Value(1)), Eq, Value(2))),
+                    ("No 3s", Compare(Count(Pattern::Value(3)), Eq, Value(0))),
+                    ("One 3", Compare(Count(Pattern::Value(3)), Eq, Value(1))),
+                    ("Two 3s", Compare(Count(Pattern::Value(3)), Eq, Value(2))),
                 ],
             )),
             46 => Ok(Card::new(
                 46,
                 [
-                    ("No 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 0
-                    }),
-                    ("One 3", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 1
-                    }),
-                    ("Two 3s", |code| {
-                        code.iter().filter(|n: &u8| *n == 3).count() == 2
-                    }),
-                    ("No 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 0
-                    }),
-                    ("One 4", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 1
-                    }),
-                    ("Two 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 2
-                    }),
+                    ("No 3s", Compare(Count(Pattern::Value(3)), Eq, Value(0))),
+                    ("One 3", Compare(Count(Pattern::Value(3)), Eq, Value(1))),
+                    ("Two 3s", Compare(Count(Pattern::Value(3)), Eq, Value(2))),
+                    ("No 4s", Compare(Count(Pattern::Value(4)), Eq, Value(0))),
+                    ("One 4", Compare(Count(Pattern::Value(4)), Eq, Value(1))),
+                    ("Two 4s", Compare(Count(Pattern::Value(4)), Eq, Value(2))),
                 ],
             )),
             47 => Ok(Card::new(
                 47,
                 [
-                    ("No 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 0
-                    }),
-                    ("One 1", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 1
-                    }),
-                    ("Two 1s", |code| {
-                        code.iter().filter(|n: &u8| *n == 1).count() == 2
-                    }),
-                    ("No 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 0
-                    }),
-                    ("One 4", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 1
-                    }),
-                    ("Two 4s", |code| {
-                        code.iter().filter(|n: &u8| *n == 4).count() == 2
-                    }),
+                    ("No 1s", Compare(Count(Pattern::Value(1)), Eq, Value(0))),
+                    ("One 1", Compare(Count(Pattern::Value(1)), Eq, Value(1))),
+                    ("Two 1s", Compare(Count(Pattern::Value(1)), Eq, Value(2))),
+                    ("No 4s", Compare(Count(Pattern::Value(4)), Eq, Value(0))),
+                    ("One 4", Compare(Count(Pattern::Value(4)), Eq, Value(1))),
+                    ("Two 4s", Compare(Count(Pattern::Value(4)), Eq, Value(2))),
                 ],
             )),
             48 => Ok(Card::new(
                 48,
                 [
-                    ("🔷 < 🟨", |code| code.blue() < code.yellow()),
-                    ("🔷 = 🟨", |code| code.blue() == code.yellow()),
-                    ("🔷 > 🟨", |code| code.blue() > code.yellow()),
-                    ("🔷 < 🟣", |code| code.blue() < code.purple()),
-                    ("🔷 = 🟣", |code| code.blue() == code.purple()),
-                    ("🔷 > 🟣", |code: &Code| code.blue() > code.purple()),
-                    ("🟨 < 🟣", |code| code.yellow() < code.purple()),
-                    ("🟨 = 🟣", |code| code.yellow() == code.purple()),
-                    ("🟨 > 🟣", |code| code.yellow() > code.purple()),
+                    ("🔷 < 🟨", Compare(B, Lt, Y)),
+                    ("🔷 = 🟨", Compare(B, Eq, Y)),
+                    ("🔷 > 🟨", Compare(B, Gt, Y)),
+                    ("🔷 < 🟣", Compare(B, Lt, P)),
+                    ("🔷 = 🟣", Compare(B, Eq, P)),
+                    ("🔷 > 🟣", Compare(B, Gt, P)),
+                    ("🟨 < 🟣", Compare(Y, Lt, P)),
+                    ("🟨 = 🟣", Compare(Y, Eq, P)),
+                    ("🟨 > 🟣", Compare(Y, Gt, P)),
                 ],
             )),
             _ => Err("No mapping for specified card number."),
         }
     }
 }
+
+#[test]
+fn card_20_counts_repeats_in_longer_codes() {
+    use crate::scenario::domain::Domain;
+    let card = Card::try_from(20).unwrap();
+    let holding = |code: &crate::scenario::code::Code| {
+        card.constraints
+            .iter()
+            .positions(|constraint| constraint.verify(code))
+            .collect_vec()
+    };
+    for code in Domain::new(4, 5).unwrap().codes() {
+        assert_eq!(holding(&code).len(), 1, "{code}");
+    }
+    for (code, idx) in [
+        ("1111", 0),
+        ("1112", 0),
+        ("1122", 1),
+        ("1213", 1),
+        ("1234", 2),
+    ] {
+        assert_eq!(holding(&code.parse().unwrap()), [idx], "{code}");
+    }
+}
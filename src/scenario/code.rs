@@ -1,41 +1,119 @@
+use serde::{Deserialize, Serialize, de::Error};
+
+/// The largest number of digits a code may have.
+pub const MAX_DIGITS: usize = 5;
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Code {
-    pub(crate) code: [u8; 3],
+    /// Digits in positional order; unused trailing digits are zero.
+    pub(crate) code: [u8; MAX_DIGITS],
+    pub(crate) len: u8,
 }
 
 impl IntoIterator for Code {
     type Item = u8;
     // TODO(perf,mem): Implement custom Iterator
-    type IntoIter = std::array::IntoIter<u8, 3>;
+    type IntoIter = std::iter::Take<std::array::IntoIter<u8, MAX_DIGITS>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.code.into_iter()
+        self.code.into_iter().take(self.len as usize)
     }
 }
 
 impl std::fmt::Display for Code {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}{}", self.blue(), self.yellow(), self.purple())
+        for digit in self.iter() {
+            write!(f, "{}", digit)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Code {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: Vec<u8> = s
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d @ 1..=9) => Ok(d as u8),
+                _ => Err("Code digits must be between 1 and 9"),
+            })
+            .collect::<Result<_, _>>()?;
+        if digits.is_empty() || digits.len() > MAX_DIGITS {
+            return Err("Code must have between 1 and 5 digits");
+        }
+        Ok(Code::new(&digits))
+    }
+}
+
+/// Codes are serialized as their digit string, e.g. `"122"`.
+impl Serialize for Code {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Code {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
     }
 }
 
 impl Code {
+    /// Convenience initializer.
+    ///
+    /// Panics if more than [`MAX_DIGITS`] digits are provided.
+    pub fn new(digits: &[u8]) -> Self {
+        let mut code = [0; MAX_DIGITS];
+        code[..digits.len()].copy_from_slice(digits);
+        Code {
+            code,
+            len: digits.len() as u8,
+        }
+    }
+
     pub fn iter(&self) -> <Code as IntoIterator>::IntoIter {
         self.into_iter()
     }
 
+    /// The number of digits in this code.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The digit at the provided position, counting from zero.
+    pub fn digit(&self, position: usize) -> u8 {
+        self.code[..self.len()][position]
+    }
+
     pub fn blue(&self) -> u8 {
-        self.code[0]
+        self.digit(0)
     }
     pub fn yellow(&self) -> u8 {
-        self.code[1]
+        self.digit(1)
     }
     pub fn purple(&self) -> u8 {
-        self.code[2]
+        self.digit(2)
     }
 }
 
 #[test]
 fn code_display() {
-    assert_eq!(Code { code: [1, 2, 3] }.to_string(), "123")
+    assert_eq!(Code::new(&[1, 2, 3]).to_string(), "123");
+    assert_eq!(Code::new(&[1, 2, 3, 6]).to_string(), "1236")
+}
+
+#[test]
+fn code_from_str() {
+    assert_eq!("345".parse(), Ok(Code::new(&[3, 4, 5])));
+    assert_eq!("3456".parse(), Ok(Code::new(&[3, 4, 5, 6])));
+    assert!("340".parse::<Code>().is_err());
+    assert!("".parse::<Code>().is_err());
 }
@@ -0,0 +1,97 @@
+use crate::scenario::{code::Code, constraint::Constraint, domain::Domain};
+
+/// A set of codes from a domain, stored as a bitset
+/// indexed by [`Domain::index`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CodeSet {
+    domain: Domain,
+    bits: Vec<u64>,
+}
+
+impl CodeSet {
+    pub fn empty(domain: Domain) -> Self {
+        CodeSet {
+            domain,
+            bits: vec![0; domain.size().div_ceil(64)],
+        }
+    }
+
+    /// Every code in the domain.
+    pub fn all(domain: Domain) -> Self {
+        CodeSet::filtered(domain, |_| true)
+    }
+
+    /// The codes of the domain accepted by the provided constraint.
+    pub fn satisfying(domain: Domain, constraint: &Constraint) -> Self {
+        CodeSet::filtered(domain, |code| constraint.verify(code))
+    }
+
+    fn filtered(domain: Domain, predicate: impl Fn(&Code) -> bool) -> Self {
+        domain
+            .codes()
+            .filter(predicate)
+            .fold(CodeSet::empty(domain), |mut set, code| {
+                set.insert(code);
+                set
+            })
+    }
+
+    pub fn insert(&mut self, code: Code) {
+        let idx = self.domain.index(&code);
+        self.bits[idx / 64] |= 1 << (idx % 64);
+    }
+
+    pub fn contains(&self, code: &Code) -> bool {
+        if !self.domain.contains(code) {
+            return false;
+        }
+        let idx = self.domain.index(code);
+        self.bits[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    pub fn intersection(&self, other: &CodeSet) -> CodeSet {
+        CodeSet {
+            domain: self.domain,
+            bits: self
+                .bits
+                .iter()
+                .zip(other.bits.iter())
+                .map(|(a, b)| a & b)
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|b| *b == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Code> + '_ {
+        self.domain.codes().filter(|code| self.contains(code))
+    }
+}
+
+#[test]
+fn code_set_intersection() {
+    let domain = Domain::default();
+    let low = CodeSet::filtered(domain, |c| c.blue() < 3);
+    let odd = CodeSet::filtered(domain, |c| c.purple() % 2 != 0);
+    let both = low.intersection(&odd);
+    assert_eq!(CodeSet::all(domain).len(), 125);
+    assert_eq!(both.len(), 2 * 5 * 3);
+    assert!(both.contains(&Code::new(&[2, 4, 5])));
+    assert!(!both.contains(&Code::new(&[3, 4, 5])));
+    assert_eq!(both.iter().count(), both.len());
+}
+
+#[test]
+fn code_set_larger_domain() {
+    let domain = Domain::new(4, 6).unwrap();
+    let sixes = CodeSet::filtered(domain, |c| c.digit(3) == 6);
+    assert_eq!(CodeSet::all(domain).len(), 1296);
+    assert_eq!(sixes.len(), 216);
+    assert!(sixes.contains(&Code::new(&[1, 2, 3, 6])));
+}
@@ -1,27 +1,82 @@
-use crate::scenario::code::Code;
+use std::sync::Arc;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+use serde::{Deserialize, Serialize, ser::SerializeStruct};
+
+use crate::{
+    render::{Locale, Render, Wording},
+    scenario::{code::Code, domain::Domain, rule::Rule},
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct ConstraintID {
     pub card: u8,
     pub idx: u8,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct Constraint {
     pub id: ConstraintID,
-    pub name: &'static str,
-    pub(crate) verifier: fn(code: &Code) -> bool,
+    pub name: Arc<str>,
+    pub rule: Rule,
+    /// Whether the constraint comes from a card file rather than
+    /// a printed card, so it has no translations.
+    pub custom: bool,
+}
+
+impl Constraint {
+    /// The wording of this constraint on the cards of a locale,
+    /// or its own name for constraints read from a card file.
+    pub fn text(&self, locale: Locale) -> &str {
+        match self.custom {
+            true => &self.name,
+            false => locale.text(self.id).unwrap_or(&self.name),
+        }
+    }
+
+    /// Whether the provided code satisfies this constraint.
+    pub fn verify(&self, code: &Code) -> bool {
+        self.rule.eval(code)
+    }
+
+    /// The codes of the domain this constraint accepts, in order.
+    pub fn accepted_codes(&self, domain: Domain) -> impl Iterator<Item = Code> + use<'_> {
+        domain.codes().filter(|code| self.verify(code))
+    }
 }
 
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Constraint {}
+
 impl std::hash::Hash for Constraint {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.id.hash(state);
     }
 }
 
+impl Serialize for Constraint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Constraint", 3)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("name", &*self.name)?;
+        state.serialize_field("rule", &self.rule.to_string())?;
+        state.end()
+    }
+}
+
+impl Render for Constraint {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
+        write!(f, "{} {}", self.id, wording.name(self))
+    }
+}
+
 impl std::fmt::Display for Constraint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.id, self.name)
+        self.render(f, Wording::default())
     }
 }
 
@@ -48,10 +103,14 @@ fn constraint_display() {
     assert_eq!(
         Constraint {
             id: ConstraintID { card: 1, idx: 3 },
-            name: "foo",
-            verifier: |_| true
+            name: "foo".into(),
+            rule: Rule::And([].into()),
+            custom: false,
         }
         .to_string(),
         "(1.3) foo"
-    )
+    );
+    let smallest = &crate::solver::constraints_for_card(14).unwrap()[1];
+    let wording = Wording::new(crate::render::Style::Ascii, Locale::De);
+    assert_eq!(wording.show(smallest).to_string(), "(14.1) Y am kleinsten");
 }
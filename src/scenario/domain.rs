@@ -0,0 +1,114 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::scenario::code::{Code, MAX_DIGITS};
+
+/// The shape of the codes being searched for: how many
+/// digits a code has and the largest value of each digit.
+///
+/// The classic game uses three digits from 1 to 5.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "DomainFields")]
+pub struct Domain {
+    digits: u8,
+    max_value: u8,
+}
+
+/// A domain as written in a file, checked by [`Domain::new`] when read.
+#[derive(Deserialize)]
+struct DomainFields {
+    digits: u8,
+    max_value: u8,
+}
+
+impl TryFrom<DomainFields> for Domain {
+    type Error = &'static str;
+
+    fn try_from(fields: DomainFields) -> Result<Self, Self::Error> {
+        Domain::new(fields.digits, fields.max_value)
+    }
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Domain {
+            digits: 3,
+            max_value: 5,
+        }
+    }
+}
+
+impl Domain {
+    /// A domain of `digits` digits, each from 1 to `max_value`.
+    ///
+    /// Codes need at least the three coloured digits the cards refer to.
+    pub fn new(digits: u8, max_value: u8) -> Result<Self, &'static str> {
+        if !(3..=MAX_DIGITS as u8).contains(&digits) {
+            return Err("Codes must have between 3 and 5 digits");
+        }
+        if !(2..=9).contains(&max_value) {
+            return Err("Digit values must range from 1 to between 2 and 9");
+        }
+        Ok(Domain { digits, max_value })
+    }
+
+    pub fn digits(&self) -> usize {
+        self.digits as usize
+    }
+
+    pub fn max_value(&self) -> u8 {
+        self.max_value
+    }
+
+    /// Number of codes in the domain.
+    pub fn size(&self) -> usize {
+        (self.max_value as usize).pow(self.digits as u32)
+    }
+
+    /// Every code in the domain, in ascending order.
+    pub fn codes(&self) -> impl Iterator<Item = Code> + use<> {
+        (0..self.digits)
+            .map(|_| 1..=self.max_value)
+            .multi_cartesian_product()
+            .map(|digits| Code::new(&digits))
+    }
+
+    pub fn contains(&self, code: &Code) -> bool {
+        code.len() == self.digits() && code.iter().all(|d| (1..=self.max_value).contains(&d))
+    }
+
+    /// Position of the provided code within [`Domain::codes`].
+    pub fn index(&self, code: &Code) -> usize {
+        code.iter().fold(0, |idx, digit| {
+            idx * self.max_value as usize + (digit - 1) as usize
+        })
+    }
+}
+
+#[test]
+fn domain_index() {
+    for domain in [Domain::default(), Domain::new(4, 6).unwrap()] {
+        assert!(
+            domain
+                .codes()
+                .enumerate()
+                .all(|(idx, code)| domain.index(&code) == idx && domain.contains(&code))
+        );
+        assert_eq!(domain.codes().count(), domain.size());
+    }
+    assert_eq!(Domain::default().size(), 125);
+    assert!(Domain::new(2, 5).is_err());
+    assert!(!Domain::default().contains(&Code::new(&[1, 2, 6])));
+}
+
+#[test]
+fn domain_deserialize_checks_limits() {
+    let read = |json| serde_json::from_str::<Domain>(json);
+    assert_eq!(
+        read(r#"{"digits": 4, "max_value": 6}"#).unwrap(),
+        Domain::new(4, 6).unwrap()
+    );
+    let error = read(r#"{"digits": 9, "max_value": 5}"#).unwrap_err();
+    assert_eq!(error.to_string(), "Codes must have between 3 and 5 digits");
+    assert!(read(r#"{"digits": 3, "max_value": 1}"#).is_err());
+}
@@ -1,3 +1,7 @@
+pub(crate) mod builder;
 pub(crate) mod card;
 pub(crate) mod code;
+pub(crate) mod code_set;
 pub(crate) mod constraint;
+pub(crate) mod domain;
+pub(crate) mod rule;
@@ -0,0 +1,294 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::scenario::code::Code;
+
+/// A comparison between two numbers.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl Op {
+    pub fn apply(self, lhs: u32, rhs: u32) -> bool {
+        match self {
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+            Op::Ge => lhs >= rhs,
+            Op::Gt => lhs > rhs,
+        }
+    }
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Ge => ">=",
+            Op::Gt => ">",
+        })
+    }
+}
+
+/// Something counted over the digits of a code.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// Digits equal to the value.
+    Value(u8),
+    /// Even digits.
+    Even,
+    /// Odd digits.
+    Odd,
+    /// Adjacent digits `(a, b)` with `a op b`.
+    Step(Op),
+    /// Any two digits `(a, b)`, left to right, with `a op b`.
+    Pair(Op),
+    /// Adjacent digits `(a, b)` with `b = a + difference`.
+    Difference(i8),
+}
+
+impl Pattern {
+    /// Whether each occurrence of the pattern in the code matches.
+    fn matches(self, code: &Code) -> Vec<bool> {
+        match self {
+            Pattern::Value(value) => code.iter().map(|d| d == value).collect(),
+            Pattern::Even => code.iter().map(|d| d % 2 == 0).collect(),
+            Pattern::Odd => code.iter().map(|d| d % 2 != 0).collect(),
+            Pattern::Step(op) => code
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| op.apply(a as u32, b as u32))
+                .collect(),
+            Pattern::Pair(op) => code
+                .iter()
+                .tuple_combinations()
+                .map(|(a, b)| op.apply(a as u32, b as u32))
+                .collect(),
+            Pattern::Difference(difference) => code
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| b as i8 - a as i8 == difference)
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Value(value) => write!(f, "{value}s"),
+            Pattern::Even => write!(f, "evens"),
+            Pattern::Odd => write!(f, "odds"),
+            Pattern::Step(op) => write!(f, "steps {op}"),
+            Pattern::Pair(op) => write!(f, "pairs {op}"),
+            Pattern::Difference(difference) => write!(f, "steps {difference:+}"),
+        }
+    }
+}
+
+/// A number computed from a code.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    Value(u8),
+    /// The digit at a position, counting from zero.
+    Digit(u8),
+    /// The sum of the digits at the positions.
+    Sum(&'static [u8]),
+    /// The sum of every digit.
+    Total,
+    /// The number of times the pattern matches.
+    Count(Pattern),
+    /// The most times any one value appears among the digits.
+    Repeats,
+}
+
+impl Expr {
+    pub fn eval(&self, code: &Code) -> u32 {
+        match self {
+            Expr::Value(value) => *value as u32,
+            Expr::Digit(position) => code.digit(*position as usize) as u32,
+            Expr::Sum(positions) => positions
+                .iter()
+                .map(|&p| code.digit(p as usize) as u32)
+                .sum(),
+            Expr::Total => code.iter().map(u32::from).sum(),
+            Expr::Count(pattern) => pattern.matches(code).into_iter().filter(|m| *m).count() as u32,
+            Expr::Repeats => code.iter().counts().into_values().max().unwrap_or_default() as u32,
+        }
+    }
+
+    /// Positions of the digits this expression reads.
+    fn positions(&self, digits: usize) -> BTreeSet<usize> {
+        match self {
+            Expr::Value(_) => BTreeSet::new(),
+            Expr::Digit(position) => [*position as usize].into(),
+            Expr::Sum(positions) => positions.iter().map(|&p| p as usize).collect(),
+            Expr::Total | Expr::Count(_) | Expr::Repeats => (0..digits).collect(),
+        }
+    }
+}
+
+/// Name of the digit at a position, by the colour of the cards.
+fn digit_name(position: u8) -> String {
+    match position {
+        0 => "B".into(),
+        1 => "Y".into(),
+        2 => "P".into(),
+        _ => format!("D{}", position + 1),
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Value(value) => write!(f, "{value}"),
+            Expr::Digit(position) => f.write_str(&digit_name(*position)),
+            Expr::Sum(positions) => {
+                f.write_str(&positions.iter().map(|&p| digit_name(p)).join(" + "))
+            }
+            Expr::Total => write!(f, "sum"),
+            Expr::Count(pattern) => write!(f, "#{pattern}"),
+            Expr::Repeats => write!(f, "repeats"),
+        }
+    }
+}
+
+/// What a constraint requires of a code.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    Compare(Expr, Op, Expr),
+    Even(Expr),
+    Odd(Expr),
+    MultipleOf(Expr, u8),
+    /// Every occurrence of the pattern matches.
+    All(Pattern),
+    And(Arc<[Rule]>),
+    Or(Arc<[Rule]>),
+    Not(Arc<Rule>),
+}
+
+impl Rule {
+    pub fn eval(&self, code: &Code) -> bool {
+        match self {
+            Rule::Compare(lhs, op, rhs) => op.apply(lhs.eval(code), rhs.eval(code)),
+            Rule::Even(expr) => expr.eval(code) % 2 == 0,
+            Rule::Odd(expr) => expr.eval(code) % 2 != 0,
+            Rule::MultipleOf(expr, n) => expr.eval(code) % *n as u32 == 0,
+            Rule::All(pattern) => pattern.matches(code).into_iter().all(|m| m),
+            Rule::And(rules) => rules.iter().all(|r| r.eval(code)),
+            Rule::Or(rules) => rules.iter().any(|r| r.eval(code)),
+            Rule::Not(rule) => !rule.eval(code),
+        }
+    }
+
+    /// Positions of the digits this rule reads
+    /// in codes of the provided length.
+    pub fn positions(&self, digits: usize) -> BTreeSet<usize> {
+        match self {
+            Rule::Compare(lhs, _, rhs) => &lhs.positions(digits) | &rhs.positions(digits),
+            Rule::Even(expr) | Rule::Odd(expr) | Rule::MultipleOf(expr, _) => {
+                expr.positions(digits)
+            }
+            Rule::All(_) => (0..digits).collect(),
+            Rule::And(rules) | Rule::Or(rules) => {
+                rules.iter().flat_map(|r| r.positions(digits)).collect()
+            }
+            Rule::Not(rule) => rule.positions(digits),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::And(rules) | Rule::Or(rules) if rules.len() > 1 => write!(f, "({self})"),
+            _ => write!(f, "{self}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::Compare(lhs, op, rhs) => write!(f, "{lhs} {op} {rhs}"),
+            Rule::Even(expr) => write!(f, "{expr} even"),
+            Rule::Odd(expr) => write!(f, "{expr} odd"),
+            Rule::MultipleOf(expr, n) => write!(f, "{expr} multiple of {n}"),
+            Rule::All(pattern) => write!(f, "all {pattern}"),
+            Rule::And(rules) if rules.is_empty() => write!(f, "true"),
+            Rule::Or(rules) if rules.is_empty() => write!(f, "false"),
+            Rule::And(rules) | Rule::Or(rules) => {
+                let joiner = if matches!(self, Rule::And(_)) {
+                    " and "
+                } else {
+                    " or "
+                };
+                for (idx, rule) in rules.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(joiner)?;
+                    }
+                    rule.fmt_operand(f)?;
+                }
+                Ok(())
+            }
+            Rule::Not(rule) => {
+                f.write_str("not ")?;
+                rule.fmt_operand(f)
+            }
+        }
+    }
+}
+
+#[test]
+fn rule_eval_and_display() {
+    use Expr::*;
+    let code = Code::new(&[1, 3, 3]);
+    let smallest = Rule::And(
+        [
+            Rule::Compare(Digit(0), Op::Lt, Digit(1)),
+            Rule::Compare(Digit(0), Op::Lt, Digit(2)),
+        ]
+        .into(),
+    );
+    assert!(smallest.eval(&code));
+    assert_eq!(smallest.to_string(), "B < Y and B < P");
+    assert_eq!(smallest.positions(3), [0, 1, 2].into());
+
+    let no_order = Rule::Not(
+        Rule::Or(
+            [
+                Rule::All(Pattern::Step(Op::Lt)),
+                Rule::All(Pattern::Step(Op::Gt)),
+            ]
+            .into(),
+        )
+        .into(),
+    );
+    assert!(no_order.eval(&code));
+    assert_eq!(no_order.to_string(), "not (all steps < or all steps >)");
+
+    let threes = Rule::Compare(Count(Pattern::Value(3)), Op::Eq, Value(2));
+    assert!(threes.eval(&code));
+    assert_eq!(threes.to_string(), "#3s = 2");
+    assert!(Rule::Compare(Sum(&[0, 2]), Op::Eq, Value(4)).eval(&code));
+    assert_eq!(Rule::Odd(Total).positions(4), [0, 1, 2, 3].into());
+
+    let double = Rule::Compare(Repeats, Op::Eq, Value(2));
+    assert!(double.eval(&code));
+    assert!(!double.eval(&Code::new(&[3, 3, 3, 1])));
+    assert_eq!(double.to_string(), "repeats = 2");
+}
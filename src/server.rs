@@ -0,0 +1,564 @@
+use std::{
+    collections::BTreeMap,
+    io::Read,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    catalogue::Catalogue,
+    guess_sequence::{Game, Guess, solver::guess_sequence_with, strategy::StrategyName},
+    solver::{Domain, GameMode, ScenarioBuilder, Solution, VerifierSpec},
+};
+
+/// A response to an API request: an HTTP status and a JSON body.
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Reply { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Reply {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+}
+
+/// The scenario a request is about, as passed on the command line.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioRequest {
+    /// Verifier specifications, e.g. `"40"`, `"40.3"` or `"^40.3"`.
+    verifiers: Vec<String>,
+    mode: Option<String>,
+    digits: Option<u8>,
+    max_value: Option<u8>,
+    /// How to pick guesses, instead of the server's strategy.
+    strategy: Option<StrategyName>,
+    /// Answers already given, e.g. `"3.0"` or `"!3.0"`; sessions only.
+    #[serde(default)]
+    answers: Vec<String>,
+}
+
+impl ScenarioRequest {
+    fn solve(&self, catalogue: &Catalogue) -> Result<Vec<Solution>, Reply> {
+        let domain = Domain::new(self.digits.unwrap_or(3), self.max_value.unwrap_or(5))
+            .map_err(|err| Reply::error(400, err))?;
+        let mode = self
+            .mode
+            .as_deref()
+            .map(str::parse::<GameMode>)
+            .transpose()
+            .map_err(|err| Reply::error(400, err))?;
+        let mut builder = ScenarioBuilder::new()
+            .domain(domain)
+            .catalogue(catalogue.clone())
+            .mode(mode);
+        for spec in &self.verifiers {
+            let spec: VerifierSpec = spec
+                .parse()
+                .map_err(|err| Reply::error(400, format!("{spec}: {err}")))?;
+            builder = builder.verifier(spec);
+        }
+        builder.solve().map_err(|err| Reply::error(400, err))
+    }
+
+    fn answers(&self) -> Result<Vec<Guess>, Reply> {
+        self.answers
+            .iter()
+            .map(|answer| {
+                answer
+                    .parse()
+                    .map_err(|err| Reply::error(400, format!("{answer}: {err}")))
+            })
+            .collect()
+    }
+}
+
+/// An answer to a guess in a session. Without a constraint,
+/// it answers the guess the decision tree recommends.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnswerRequest {
+    constraint: Option<String>,
+    verified: bool,
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, Reply> {
+    serde_json::from_str(body).map_err(|err| Reply::error(400, err))
+}
+
+/// The JSON API: solving scenarios, building decision trees
+/// and playing games in sessions kept between requests.
+///
+/// | Request | Body | Does |
+/// | --- | --- | --- |
+/// | `POST /solve` | scenario | List the solutions |
+/// | `POST /tree` | scenario | Build the decision tree |
+/// | `POST /sessions` | scenario | Start a game |
+/// | `GET /sessions/{id}` | | Show where a game stands |
+/// | `POST /sessions/{id}/answers` | answer | Record an answer |
+/// | `POST /sessions/{id}/undo` | | Take back the last answer |
+/// | `DELETE /sessions/{id}` | | End a game |
+///
+/// A scenario looks like `{"verifiers": ["3", "10", "14", "17"]}`, with
+/// optional `mode`, `digits`, `max_value`, `strategy` and, for sessions,
+/// `answers` such as `["3.0", "!10.1"]`. An answer looks like
+/// `{"verified": true}`, with an optional `constraint` such as `"3.0"`
+/// when answering a guess other than the recommended one.
+///
+/// Sessions have random ids, so one client cannot guess another's.
+/// Sessions left idle for longer than [`Api::idle`] are ended, and
+/// starting a session beyond [`Api::max_sessions`] ends the one
+/// idle the longest.
+pub struct Api {
+    catalogue: Catalogue,
+    strategy: StrategyName,
+    max_sessions: usize,
+    idle: Duration,
+    origins: Vec<String>,
+    sessions: Mutex<BTreeMap<String, Session>>,
+}
+
+struct Session {
+    /// Locked on its own, so a slow game does not hold up the others.
+    game: Arc<Mutex<Game>>,
+    used: Instant,
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Api {
+            catalogue: Catalogue::default(),
+            strategy: StrategyName::default(),
+            max_sessions: Self::MAX_SESSIONS,
+            idle: Self::IDLE,
+            origins: vec![],
+            sessions: Mutex::default(),
+        }
+    }
+}
+
+impl Api {
+    /// The number of sessions kept by default.
+    pub const MAX_SESSIONS: usize = 1000;
+    /// How long a session is kept without requests by default.
+    pub const IDLE: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new() -> Self {
+        Api::default()
+    }
+
+    /// Take the verifier cards from the catalogue rather
+    /// than from the printed cards only.
+    pub fn catalogue(mut self, catalogue: Catalogue) -> Self {
+        self.catalogue = catalogue;
+        self
+    }
+
+    /// Pick guesses with the strategy when a request names none.
+    pub fn strategy(mut self, strategy: StrategyName) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Keep at most this many sessions, and at least one.
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    /// End sessions that receive no request for this long.
+    pub fn idle(mut self, idle: Duration) -> Self {
+        self.idle = idle;
+        self
+    }
+
+    /// Let web pages from the origin, e.g. `http://localhost:8080`,
+    /// call the API. Requests from other origins are refused.
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origins.push(origin.into());
+        self
+    }
+
+    /// Whether web pages from the origin may call the API.
+    pub fn allows(&self, origin: &str) -> bool {
+        self.origins.iter().any(|allowed| allowed == origin)
+    }
+
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> Reply {
+        let path = path.split('?').next().unwrap_or_default();
+        let segments = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let result = match (method, segments.as_slice()) {
+            // A browser asking whether it may send the request,
+            // which the CORS headers of the response answer.
+            ("OPTIONS", _) => Ok(Reply {
+                status: 204,
+                body: Value::Null,
+            }),
+            ("POST", ["solve"]) => parse(body).and_then(|request| self.solve(request)),
+            ("POST", ["tree"]) => parse(body).and_then(|request| self.tree(request)),
+            ("POST", ["sessions"]) => parse(body).and_then(|request| self.start(request)),
+            (method, ["sessions", id, rest @ ..]) => self.session(method, id, rest, body),
+            _ => Err(Reply::error(404, format!("No route for {method} {path}"))),
+        };
+        result.unwrap_or_else(|reply| reply)
+    }
+
+    fn solve(&self, request: ScenarioRequest) -> Result<Reply, Reply> {
+        Ok(Reply::ok(
+            json!({ "solutions": request.solve(&self.catalogue)? }),
+        ))
+    }
+
+    fn tree(&self, request: ScenarioRequest) -> Result<Reply, Reply> {
+        let solutions = request.solve(&self.catalogue)?;
+        if solutions.is_empty() {
+            return Err(Reply::error(422, "No possible solutions"));
+        }
+        let strategy = request.strategy.unwrap_or(self.strategy);
+        let (tree, complete) = match guess_sequence_with(&solutions, strategy.picker(None)) {
+            Ok(tree) => (tree, true),
+            Err(incomplete) => (*incomplete.tree, false),
+        };
+        Ok(Reply::ok(json!({
+            "tree": tree.to_outcome(),
+            "stats": tree.stats(),
+            "complete": complete,
+        })))
+    }
+
+    fn start(&self, request: ScenarioRequest) -> Result<Reply, Reply> {
+        let answers = request.answers()?;
+        let solutions = request.solve(&self.catalogue)?;
+        if solutions.is_empty() {
+            return Err(Reply::error(422, "No possible solutions"));
+        }
+        let strategy = request.strategy.unwrap_or(self.strategy);
+        let tree = guess_sequence_with(&solutions, strategy.picker(None))
+            .unwrap_or_else(|incomplete| *incomplete.tree);
+        let mut game = Game::new(tree);
+        for answer in answers {
+            game.record(answer);
+        }
+        let game = Arc::new(Mutex::new(game));
+        let id = {
+            let mut sessions = self.sessions();
+            if sessions.len() >= self.max_sessions
+                && let Some(oldest) = sessions
+                    .iter()
+                    .min_by_key(|(_, session)| session.used)
+                    .map(|(id, _)| id.clone())
+            {
+                sessions.remove(&oldest);
+            }
+            let id = std::iter::repeat_with(|| format!("{:032x}", rand::random::<u128>()))
+                .find(|id| !sessions.contains_key(id))
+                .expect("ids are endless");
+            sessions.insert(
+                id.clone(),
+                Session {
+                    game: game.clone(),
+                    used: Instant::now(),
+                },
+            );
+            id
+        };
+        let game = game.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(Reply {
+            status: 201,
+            body: state(&id, &game),
+        })
+    }
+
+    /// The sessions, without those left idle for too long.
+    fn sessions(&self) -> MutexGuard<'_, BTreeMap<String, Session>> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|err| err.into_inner());
+        sessions.retain(|_, session| session.used.elapsed() < self.idle);
+        sessions
+    }
+
+    fn session(&self, method: &str, id: &str, rest: &[&str], body: &str) -> Result<Reply, Reply> {
+        let game = {
+            let mut sessions = self.sessions();
+            if (method, rest) == ("DELETE", &[][..]) && sessions.remove(id).is_some() {
+                return Ok(Reply::ok(json!({ "id": id })));
+            }
+            let session = sessions
+                .get_mut(id)
+                .ok_or_else(|| Reply::error(404, format!("There is no session {id}")))?;
+            session.used = Instant::now();
+            session.game.clone()
+        };
+        let mut game = game.lock().unwrap_or_else(|err| err.into_inner());
+        match (method, rest) {
+            ("GET", []) => {}
+            ("POST", ["answers"]) => {
+                let request: AnswerRequest = parse(body)?;
+                match request.constraint {
+                    Some(constraint) => {
+                        let mut answer: Guess = constraint
+                            .parse()
+                            .map_err(|err| Reply::error(400, format!("{constraint}: {err}")))?;
+                        answer.verified = request.verified;
+                        game.record(answer);
+                    }
+                    None => {
+                        game.answer(request.verified)
+                            .ok_or_else(|| Reply::error(409, "There is no guess left to answer"))?;
+                    }
+                }
+            }
+            ("POST", ["undo"]) => {
+                game.undo()
+                    .ok_or_else(|| Reply::error(409, "There is no answer to take back"))?;
+            }
+            _ => return Err(Reply::error(404, "No such session route")),
+        }
+        Ok(Reply::ok(state(id, &game)))
+    }
+}
+
+/// Where the game of a session stands.
+fn state(id: &str, game: &Game) -> Value {
+    let position = game.position();
+    let verifiers = game
+        .verifiers()
+        .into_iter()
+        .map(|verifier| {
+            json!({
+                "card": verifier.card,
+                "constraints": verifier.constraints.into_iter().map(|(constraint, possible)| json!({
+                    "id": constraint.id,
+                    "name": constraint.name,
+                    "possible": possible,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "id": id,
+        "answers": game.answers(),
+        "next_guess": position.next_guess,
+        "candidates": position.candidates,
+        "solution": position.solution(),
+        "verifiers": verifiers,
+    })
+}
+
+/// The largest request body read, in bytes.
+const MAX_BODY: usize = 64 * 1024;
+
+/// The CORS headers letting a web page from an allowed origin,
+/// such as a web scoreboard, call the API.
+const CORS_HEADERS: [(&str, &str); 3] = [
+    ("Access-Control-Allow-Methods", "GET, POST, DELETE, OPTIONS"),
+    ("Access-Control-Allow-Headers", "Content-Type"),
+    ("Vary", "Origin"),
+];
+
+/// Serve the API over HTTP at the address until the process ends,
+/// handling requests on a worker thread per available core.
+pub fn serve(address: &str, api: Api) -> std::io::Result<()> {
+    let server = tiny_http::Server::http(address).map_err(std::io::Error::other)?;
+    let workers = std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    respond(&api, request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn header(name: &str, value: &str) -> tiny_http::Header {
+    tiny_http::Header::from_bytes(name, value).expect("valid header")
+}
+
+fn respond(api: &Api, mut request: tiny_http::Request) {
+    // Browsers name the page a request comes from, while other
+    // clients such as curl send no origin and are always served.
+    let origin = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Origin"))
+        .map(|header| header.value.to_string());
+    let mut body = String::new();
+    let reply = match &origin {
+        Some(origin) if !api.allows(origin) => {
+            Reply::error(403, format!("Requests from {origin} are not allowed"))
+        }
+        _ => match request
+            .as_reader()
+            .take(MAX_BODY as u64 + 1)
+            .read_to_string(&mut body)
+        {
+            Ok(len) if len > MAX_BODY => {
+                Reply::error(413, format!("The body is longer than {MAX_BODY} bytes"))
+            }
+            Ok(_) => api.handle(request.method().as_str(), request.url(), &body),
+            Err(err) => Reply::error(400, err),
+        },
+    };
+    log::info!("{} {} {}", request.method(), request.url(), reply.status);
+    let body = match reply.body {
+        Value::Null => String::new(),
+        body => body.to_string(),
+    };
+    let mut response = tiny_http::Response::from_string(body)
+        .with_status_code(reply.status)
+        .with_header(header("Content-Type", "application/json"));
+    if let Some(origin) = origin.filter(|origin| api.allows(origin)) {
+        response.add_header(header("Access-Control-Allow-Origin", &origin));
+        for (name, value) in CORS_HEADERS {
+            response.add_header(header(name, value));
+        }
+    }
+    if let Err(err) = request.respond(response) {
+        log::warn!("Could not respond: {err}");
+    }
+}
+
+#[test]
+fn api_plays_a_session() {
+    let api = Api::new();
+    let scenario = r#"{"verifiers": ["3", "10", "14", "17"]}"#;
+    let solved = api.handle("POST", "/solve", scenario);
+    assert_eq!(solved.status, 200);
+    assert_eq!(solved.body["solutions"].as_array().unwrap().len(), 16);
+    let tree = api.handle("POST", "/tree", scenario);
+    assert_eq!(tree.body["complete"], true);
+    let minimax = api.handle(
+        "POST",
+        "/tree",
+        r#"{"verifiers": ["3", "10", "14", "17"], "strategy": "minimax"}"#,
+    );
+    assert!(
+        minimax.body["stats"]["worst_case_path_length"]
+            .as_u64()
+            .unwrap()
+            <= tree.body["stats"]["worst_case_path_length"]
+                .as_u64()
+                .unwrap()
+    );
+
+    let started = api.handle("POST", "/sessions", scenario);
+    assert_eq!(started.status, 201);
+    let id = started.body["id"].as_str().unwrap().to_owned();
+    assert_eq!(started.body["candidates"].as_array().unwrap().len(), 16);
+    let mut state = started.body;
+    while state["solution"].is_null() {
+        state = api
+            .handle(
+                "POST",
+                &format!("/sessions/{id}/answers"),
+                r#"{"verified": false}"#,
+            )
+            .body;
+    }
+    assert!(state["next_guess"].is_null());
+    let answered = state["answers"].as_array().unwrap().len();
+    let undone = api.handle("POST", &format!("/sessions/{id}/undo"), "");
+    assert_eq!(
+        undone.body["answers"].as_array().unwrap().len(),
+        answered - 1
+    );
+
+    let answer = api.handle(
+        "POST",
+        &format!("/sessions/{id}/answers"),
+        r#"{"constraint": "17.2", "verified": true}"#,
+    );
+    assert_eq!(answer.status, 200);
+    assert_eq!(
+        api.handle("DELETE", &format!("/sessions/{id}"), "").status,
+        200
+    );
+    assert_eq!(
+        api.handle("GET", &format!("/sessions/{id}"), "").status,
+        404
+    );
+}
+
+#[test]
+fn api_reports_bad_requests() {
+    let api = Api::new();
+    let reply = api.handle(
+        "POST",
+        "/solve",
+        r#"{"verifiers": ["3", "10", "14"], "mode": "classic"}"#,
+    );
+    assert_eq!(
+        reply,
+        Reply::error(400, "A classic game has 4 to 6 verifiers, not 3")
+    );
+    assert_eq!(api.handle("POST", "/solve", "{").status, 400);
+    assert_eq!(
+        api.handle("POST", "/solve", r#"{"verifiers": ["x"]}"#)
+            .status,
+        400
+    );
+    assert_eq!(
+        api.handle(
+            "POST",
+            "/tree",
+            r#"{"verifiers": ["3"], "strategy": "luck"}"#
+        )
+        .status,
+        400
+    );
+    assert_eq!(api.handle("GET", "/nowhere", "").status, 404);
+    assert_eq!(api.handle("OPTIONS", "/sessions", "").status, 204);
+}
+
+#[test]
+fn api_ends_old_sessions() {
+    let scenario = r#"{"verifiers": ["3", "10", "14", "17"]}"#;
+    let start = |api: &Api| {
+        api.handle("POST", "/sessions", scenario).body["id"]
+            .as_str()
+            .unwrap()
+            .to_owned()
+    };
+    let get = |api: &Api, id: &str| api.handle("GET", &format!("/sessions/{id}"), "").status;
+
+    let api = Api::new().max_sessions(2);
+    let first = start(&api);
+    let second = start(&api);
+    assert_ne!(first, second);
+    assert_eq!(get(&api, &first), 200);
+    let third = start(&api);
+    assert_eq!(get(&api, &second), 404);
+    assert_eq!(get(&api, &first), 200);
+    assert_eq!(get(&api, &third), 200);
+
+    let api = Api::new().idle(Duration::ZERO);
+    let id = start(&api);
+    assert_eq!(get(&api, &id), 404);
+}
+
+#[test]
+fn api_allows_listed_origins() {
+    let api = Api::new().allow_origin("http://localhost:8080");
+    assert!(api.allows("http://localhost:8080"));
+    assert!(!api.allows("http://localhost:8081"));
+    assert!(!Api::new().allows("http://localhost:8080"));
+}
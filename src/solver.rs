@@ -1,9 +1,16 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
-pub use super::scenario::constraint::Constraint;
-use crate::scenario::{card::Card, code::Code};
+pub use super::scenario::constraint::{Constraint, ConstraintID};
+use crate::render::{Render, Wording};
+pub use crate::scenario::builder::{
+    GameMode, ScenarioBuilder, ScenarioError, Selection, VerifierSpec, verifier_limits,
+};
+pub use crate::scenario::domain::Domain;
+pub use crate::scenario::rule::{Expr, Op, Pattern, Rule};
+use crate::scenario::{card::Card, code::Code, code_set::CodeSet};
 use itertools::Itertools;
 use log::debug;
+use serde::{Serialize, ser::SerializeStruct};
 
 #[derive(Clone)]
 pub struct Solution {
@@ -11,43 +18,213 @@ pub struct Solution {
     pub(crate) constraints: HashSet<Constraint>,
 }
 
-impl std::fmt::Display for Solution {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Solution {
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Whether this solution relies on the identified constraint.
+    pub fn contains(&self, id: ConstraintID) -> bool {
+        self.constraints.iter().any(|c| c.id == id)
+    }
+
+    /// The constraints of this solution, ordered by id.
+    pub fn sorted_constraints(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter().sorted_by(|&a, &b| a.id.cmp(&b.id))
+    }
+}
+
+impl Serialize for Solution {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Solution", 2)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("constraints", &self.sorted_constraints().collect_vec())?;
+        state.end()
+    }
+}
+
+impl Render for Solution {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
         let constraints = self
-            .constraints
-            .iter()
-            .sorted_by(|&a, &b| a.id.cmp(&b.id))
-            .map(ToString::to_string)
+            .sorted_constraints()
+            .map(|c| wording.show(c))
             .join("; ");
         write!(f, "({}) with {constraints}", self.code)
     }
 }
 
+impl std::fmt::Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, Wording::default())
+    }
+}
+
+/// The solutions sharing a single code, each being
+/// a distinct interpretation of the verifiers.
+#[derive(Serialize)]
+pub struct CodeSolutions {
+    pub code: Code,
+    pub solutions: Vec<Solution>,
+}
+
+impl Render for CodeSolutions {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>, wording: Wording) -> std::fmt::Result {
+        writeln!(f, "({})", self.code)?;
+        for solution in &self.solutions {
+            let constraints = solution
+                .sorted_constraints()
+                .map(|c| wording.show(c))
+                .join("; ");
+            writeln!(f, "\t{constraints}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for CodeSolutions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, Wording::default())
+    }
+}
+
+/// Group solutions by code, keeping codes in
+/// the order they first appear.
+pub fn group_by_code(solutions: &[Solution]) -> Vec<CodeSolutions> {
+    solutions
+        .iter()
+        .fold(Vec::<CodeSolutions>::new(), |mut groups, solution| {
+            match groups.iter_mut().find(|g| g.code == solution.code) {
+                Some(group) => group.solutions.push(solution.clone()),
+                None => groups.push(CodeSolutions {
+                    code: solution.code,
+                    solutions: vec![solution.clone()],
+                }),
+            }
+            groups
+        })
+}
+
 #[cfg(test)]
 mod test {
     use crate::scenario::code::Code;
     use crate::scenario::constraint::{Constraint, ConstraintID};
+    use crate::scenario::rule::Rule;
     use crate::solver::Solution;
     #[test]
     fn solution_display() {
         let solution = Solution {
-            code: Code { code: [1, 2, 3] },
+            code: Code::new(&[1, 2, 3]),
             constraints: [
                 Constraint {
                     id: ConstraintID { card: 1, idx: 3 },
-                    name: "foo",
-                    verifier: |_| true,
+                    name: "foo".into(),
+                    rule: Rule::And([].into()),
+                    custom: false,
                 },
                 Constraint {
                     id: ConstraintID { card: 2, idx: 1 },
-                    name: "bar",
-                    verifier: |_| true,
+                    name: "bar".into(),
+                    rule: Rule::And([].into()),
+                    custom: false,
                 },
             ]
             .into(),
         };
         assert_eq!(solution.to_string(), "(123) with (1.3) foo; (2.1) bar")
     }
+
+    #[test]
+    fn group_by_code_display() {
+        use crate::solver::group_by_code;
+        let constraint = |card, idx, name: &str| Constraint {
+            id: ConstraintID { card, idx },
+            name: name.into(),
+            rule: Rule::And([].into()),
+            custom: false,
+        };
+        let solutions = [
+            Solution {
+                code: Code::new(&[1, 2, 3]),
+                constraints: [constraint(1, 0, "foo"), constraint(2, 0, "bar")].into(),
+            },
+            Solution {
+                code: Code::new(&[3, 2, 1]),
+                constraints: [constraint(1, 1, "baz"), constraint(2, 0, "bar")].into(),
+            },
+            Solution {
+                code: Code::new(&[1, 2, 3]),
+                constraints: [constraint(1, 0, "foo"), constraint(2, 1, "qux")].into(),
+            },
+        ];
+        let groups = group_by_code(&solutions);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0].to_string(),
+            "(123)\n\t(1.0) foo; (2.0) bar\n\t(1.0) foo; (2.1) qux\n"
+        );
+        assert_eq!(groups[1].to_string(), "(321)\n\t(1.1) baz; (2.0) bar\n");
+    }
+
+    #[test]
+    fn search_matches_exhaustive_enumeration() {
+        use crate::solver::{Domain, constraints_for_card, group_by_card, solve_sequential};
+        use itertools::Itertools;
+        let groups = group_by_card(
+            [3, 10, 14, 17, 40]
+                .into_iter()
+                .flat_map(|card| constraints_for_card(card).unwrap())
+                .collect(),
+        );
+        let exhaustive = groups
+            .iter()
+            .map(|group| group.constraints.iter())
+            .multi_cartesian_product()
+            .filter_map(|combo| {
+                Domain::default()
+                    .codes()
+                    .filter(|code| combo.iter().all(|c| c.verify(code)))
+                    .exactly_one()
+                    .ok()
+                    .map(|code| Solution {
+                        code,
+                        constraints: combo.into_iter().cloned().collect(),
+                    })
+            })
+            .map(|s| s.to_string())
+            .sorted()
+            .collect_vec();
+        let searched = solve_sequential(Domain::default(), &groups)
+            .iter()
+            .map(ToString::to_string)
+            .sorted()
+            .collect_vec();
+        assert!(!searched.is_empty());
+        assert_eq!(exhaustive, searched);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_sequential() {
+        use crate::solver::{
+            Domain, constraints_for_card, group_by_card, solve_parallel, solve_sequential,
+        };
+        let groups = group_by_card(
+            [40, 10, 14, 17, 48]
+                .into_iter()
+                .flat_map(|card| constraints_for_card(card).unwrap())
+                .collect(),
+        );
+        let sequential = solve_sequential(Domain::default(), &groups);
+        let parallel = solve_parallel(Domain::default(), &groups);
+        assert!(!sequential.is_empty());
+        assert_eq!(
+            sequential
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            parallel.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
 }
 
 #[derive(Clone)]
@@ -55,14 +232,14 @@ struct ConstraintGroup {
     constraints: Vec<Constraint>,
 }
 
-impl Into<Vec<Constraint>> for Card {
-    fn into(self) -> Vec<Constraint> {
-        self.constraints
+impl From<Card> for Vec<Constraint> {
+    fn from(val: Card) -> Self {
+        val.constraints
     }
 }
-impl Into<Vec<Constraint>> for Constraint {
-    fn into(self) -> Vec<Constraint> {
-        vec![self]
+impl From<Constraint> for Vec<Constraint> {
+    fn from(val: Constraint) -> Self {
+        vec![val]
     }
 }
 
@@ -71,56 +248,188 @@ pub fn constraints_for_card(card_num: u8) -> Option<Vec<Constraint>> {
 }
 
 pub fn turing_solve(constraints: Vec<Constraint>) -> Vec<Solution> {
-    let constraint_groups: Vec<ConstraintGroup> = constraints
-        .iter()
-        .fold(HashMap::<u8, Vec<Constraint>>::new(), |mut map, c| {
-            map.entry(c.id.card).or_insert(vec![]).push(*c);
+    turing_solve_in(Domain::default(), constraints)
+}
+
+/// The solutions of the README example, `3 10 14 17`, shared by tests.
+#[cfg(test)]
+pub(crate) fn example_solutions() -> Vec<Solution> {
+    turing_solve(
+        [3, 10, 14, 17]
+            .into_iter()
+            .flat_map(|card| constraints_for_card(card).unwrap())
+            .collect(),
+    )
+}
+
+/// Solve for codes of the provided domain rather than
+/// the classic three digits from 1 to 5.
+pub fn turing_solve_in(domain: Domain, constraints: Vec<Constraint>) -> Vec<Solution> {
+    let constraint_groups = group_by_card(constraints);
+    #[cfg(feature = "parallel")]
+    {
+        solve_parallel(domain, &constraint_groups)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        solve_sequential(domain, &constraint_groups)
+    }
+}
+
+/// Group constraints into one group per verifier card,
+/// ordered by card number so that enumeration order
+/// is stable between runs.
+fn group_by_card(constraints: Vec<Constraint>) -> Vec<ConstraintGroup> {
+    constraints
+        .into_iter()
+        .fold(BTreeMap::<u8, Vec<Constraint>>::new(), |mut map, c| {
+            map.entry(c.id.card).or_default().push(c);
             map
         })
         .into_values()
-        .map(|constraints| ConstraintGroup {
-            constraints: constraints,
-        })
-        .collect();
+        .map(|constraints| ConstraintGroup { constraints })
+        .collect()
+}
+
+/// Constraints of one verifier that accept exactly the same codes.
+///
+/// Equivalent constraints lead to identical searches, so
+/// the search visits each class once and only expands it
+/// into individual constraints when a solution is found.
+struct EquivalenceClass<'a> {
+    codes: CodeSet,
+    constraints: Vec<&'a Constraint>,
+}
+
+/// Compute the accepted codes of every constraint once,
+/// dropping constraints that accept no code at all and
+/// merging constraints that accept the same codes.
+fn prepare(
+    domain: Domain,
+    constraint_groups: &[ConstraintGroup],
+) -> Vec<Vec<EquivalenceClass<'_>>> {
     constraint_groups
         .iter()
-        .map(|group| group.constraints.iter())
-        .multi_cartesian_product()
-        .filter_map(|constraint_combo| {
-            let possible_codes: Vec<Code> = (1u8..=5)
-                .cartesian_product(1u8..=5)
-                .cartesian_product(1u8..=5)
-                .filter_map(|((blue, yellow), purple)| {
-                    let code = Code {
-                        code: [blue, yellow, purple],
-                    };
-                    if constraint_combo.iter().all(|c| (c.verifier)(&code)) {
-                        debug!(
-                            "{} passed {}",
-                            code,
-                            constraint_combo.iter().map(ToString::to_string).join("; ")
-                        );
-                        Some(code)
+        .map(|group| {
+            group.constraints.iter().fold(
+                Vec::<EquivalenceClass>::new(),
+                |mut classes, constraint| {
+                    let codes = CodeSet::satisfying(domain, constraint);
+                    if codes.is_empty() {
+                        debug!("{} accepts no code", constraint);
+                    } else if let Some(class) = classes.iter_mut().find(|c| c.codes == codes) {
+                        class.constraints.push(constraint);
                     } else {
-                        debug!(
-                            "{} failed {}",
-                            code,
-                            constraint_combo.iter().map(ToString::to_string).join("; ")
-                        );
-                        None
+                        classes.push(EquivalenceClass {
+                            codes,
+                            constraints: vec![constraint],
+                        });
                     }
-                })
-                .collect();
-            if possible_codes.len() > 1 {
-                return None;
-            }
-            if let Some(code) = possible_codes.first() {
-                return Some(Solution {
-                    code: *code,
-                    constraints: constraint_combo.into_iter().copied().collect(),
-                });
-            }
-            return None;
+                    classes
+                },
+            )
         })
         .collect()
 }
+
+fn solve_sequential(domain: Domain, constraint_groups: &[ConstraintGroup]) -> Vec<Solution> {
+    let groups = prepare(domain, constraint_groups);
+    let mut solutions = vec![];
+    search(&groups, &CodeSet::all(domain), &mut vec![], &mut solutions);
+    solutions
+}
+
+/// Enumerate the same solutions as [`solve_sequential`],
+/// partitioning the first verifier's constraints across worker threads.
+///
+/// Each worker owns a contiguous slice of the first verifier and the
+/// partial results are concatenated in slice order, so the output
+/// is identical to the sequential search.
+#[cfg(feature = "parallel")]
+fn solve_parallel(domain: Domain, constraint_groups: &[ConstraintGroup]) -> Vec<Solution> {
+    let groups = prepare(domain, constraint_groups);
+    let Some((first, rest)) = groups.split_first() else {
+        return solve_sequential(domain, constraint_groups);
+    };
+    let workers = std::thread::available_parallelism()
+        .map(std::num::NonZeroUsize::get)
+        .unwrap_or(1)
+        .min(first.len());
+    if workers <= 1 {
+        return solve_sequential(domain, constraint_groups);
+    }
+    let chunk_size = first.len().div_ceil(workers);
+    let all_codes = CodeSet::all(domain);
+    std::thread::scope(|scope| {
+        first
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let all_codes = &all_codes;
+                scope.spawn(move || {
+                    let mut solutions = vec![];
+                    for class in chunk {
+                        descend(class, rest, all_codes, &mut vec![], &mut solutions);
+                    }
+                    solutions
+                })
+            })
+            .collect_vec()
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("solver worker panicked"))
+            .collect()
+    })
+}
+
+/// Choose a constraint for each remaining verifier in turn,
+/// abandoning a branch as soon as no code satisfies every
+/// constraint chosen so far.
+fn search<'a>(
+    groups: &'a [Vec<EquivalenceClass<'a>>],
+    possible_codes: &CodeSet,
+    chosen: &mut Vec<&'a EquivalenceClass<'a>>,
+    solutions: &mut Vec<Solution>,
+) {
+    let Some((group, rest)) = groups.split_first() else {
+        if possible_codes.len() == 1 {
+            let code = possible_codes.iter().next().expect("one possible code");
+            solutions.extend(
+                chosen
+                    .iter()
+                    .map(|class| class.constraints.iter())
+                    .multi_cartesian_product()
+                    .map(|constraint_combo| Solution {
+                        code,
+                        constraints: constraint_combo.into_iter().map(|c| (*c).clone()).collect(),
+                    }),
+            );
+        }
+        return;
+    };
+    for class in group {
+        descend(class, rest, possible_codes, chosen, solutions);
+    }
+}
+
+fn descend<'a>(
+    class: &'a EquivalenceClass<'a>,
+    rest: &'a [Vec<EquivalenceClass<'a>>],
+    possible_codes: &CodeSet,
+    chosen: &mut Vec<&'a EquivalenceClass<'a>>,
+    solutions: &mut Vec<Solution>,
+) {
+    let possible_codes = possible_codes.intersection(&class.codes);
+    if possible_codes.is_empty() {
+        debug!(
+            "Pruned {}",
+            chosen
+                .iter()
+                .chain(std::iter::once(&class))
+                .map(|c| c.constraints[0].to_string())
+                .join("; ")
+        );
+        return;
+    }
+    chosen.push(class);
+    search(rest, &possible_codes, chosen, solutions);
+    chosen.pop();
+}
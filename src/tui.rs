@@ -0,0 +1,209 @@
+use itertools::Itertools;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint as Size, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+};
+
+use crate::{
+    guess_sequence::{Game, Proposal},
+    render::Wording,
+    scenario::{constraint::ConstraintID, domain::Domain},
+};
+
+/// Play the game full screen until the player quits, proposing
+/// codes of the domain and reading the verifiers' answers from
+/// the keyboard.
+pub fn run(game: &mut Game, domain: Domain, wording: Wording) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, game, domain, wording);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    game: &mut Game,
+    domain: Domain,
+    wording: Wording,
+) -> std::io::Result<()> {
+    loop {
+        let proposal = game.proposal(domain);
+        terminal.draw(|frame| draw(frame, game, proposal, wording))?;
+        // With a proposal, the keys give the verifier's answer to the
+        // code; without one, whether the constraint itself holds.
+        let verified = |accepted| proposal.map_or(accepted, |p| p.verified(accepted));
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('y' | 'v') => {
+                    game.answer(verified(true));
+                }
+                KeyCode::Char('n' | 'x') => {
+                    game.answer(verified(false));
+                }
+                KeyCode::Char('u') | KeyCode::Backspace => {
+                    game.undo();
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A constraint of the game as written on its card.
+fn constraint_text(game: &Game, id: ConstraintID, wording: Wording) -> String {
+    match game.tree().constraint(id) {
+        Some(constraint) => wording.show(constraint).to_string(),
+        None => id.to_string(),
+    }
+}
+
+fn draw(frame: &mut Frame, game: &Game, proposal: Option<Proposal>, wording: Wording) {
+    let [main, keys] = Layout::vertical([Size::Fill(1), Size::Length(1)]).areas(frame.area());
+    let [verifiers, middle, history] =
+        Layout::horizontal([Size::Percentage(35), Size::Fill(1), Size::Percentage(30)]).areas(main);
+    let [next, codes] = Layout::vertical([Size::Length(5), Size::Fill(1)]).areas(middle);
+    let position = game.position();
+
+    let lines = game.verifiers().into_iter().flat_map(|verifier| {
+        let constraints = verifier
+            .constraints
+            .into_iter()
+            .map(|(constraint, possible)| {
+                let style = match possible {
+                    true => Style::new(),
+                    false => Style::new()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                };
+                Line::styled(format!("  {}", wording.show(constraint)), style)
+            });
+        std::iter::once(Line::styled(
+            format!("Card {}", verifier.card),
+            Style::new().add_modifier(Modifier::BOLD),
+        ))
+        .chain(constraints)
+        .collect_vec()
+    });
+    frame.render_widget(
+        Paragraph::new(lines.collect_vec()).block(Block::bordered().title("Verifiers")),
+        verifiers,
+    );
+
+    let bold = Style::new().add_modifier(Modifier::BOLD);
+    let advice = match (position.solution(), position.next_guess) {
+        (Some(code), _) => vec![Line::styled(
+            format!("The code is {code}"),
+            Style::new().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )],
+        (None, Some(id)) => match proposal {
+            Some(proposal) => vec![
+                Line::from(vec![
+                    Span::raw("Propose "),
+                    Span::styled(proposal.code.to_string(), bold),
+                    Span::raw(format!(" to verifier {}", id.card)),
+                ]),
+                Line::from(vec![
+                    Span::styled(constraint_text(game, id, wording), bold),
+                    Span::raw(match proposal.accepted {
+                        true => " holds if ✓",
+                        false => " holds if ✗",
+                    }),
+                ]),
+            ],
+            None => vec![Line::from(vec![
+                Span::raw("Test "),
+                Span::styled(constraint_text(game, id, wording), bold),
+            ])],
+        },
+        (None, None) if position.candidates.is_empty() => vec![Line::styled(
+            "The answers rule out every solution",
+            Style::new().fg(Color::Red),
+        )],
+        (None, None) => vec![Line::styled(
+            "No guess tells the remaining codes apart",
+            Style::new().fg(Color::Red),
+        )],
+    };
+    frame.render_widget(
+        Paragraph::new(advice)
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title("Next proposal")),
+        next,
+    );
+
+    frame.render_widget(
+        Paragraph::new(position.candidates.iter().join(" "))
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered().title(format!("Remaining codes ({})", position.candidates.len())),
+            ),
+        codes,
+    );
+
+    let answers = game
+        .answers()
+        .iter()
+        .map(|answer| {
+            let (mark, colour) = match answer.verified {
+                true => ("✓", Color::Green),
+                false => ("✗", Color::Red),
+            };
+            Line::from(vec![
+                Span::styled(mark, Style::new().fg(colour)),
+                Span::raw(format!(
+                    " {}",
+                    constraint_text(game, answer.constraint, wording)
+                )),
+            ])
+        })
+        .collect_vec();
+    frame.render_widget(
+        Paragraph::new(answers).block(Block::bordered().title("History")),
+        history,
+    );
+
+    frame.render_widget(
+        Paragraph::new("y ✓ verified   n ✗ rejected   u undo   q quit")
+            .style(Style::new().add_modifier(Modifier::REVERSED)),
+        keys,
+    );
+}
+
+#[test]
+fn draw_shows_every_pane() {
+    use ratatui::{Terminal, backend::TestBackend};
+    let mut game = Game::new(crate::guess_sequence::example_tree());
+    game.answer(true);
+    let proposal = game.proposal(Domain::default()).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal
+        .draw(|frame| draw(frame, &game, Some(proposal), Wording::default()))
+        .unwrap();
+    let screen = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    for text in [
+        "Verifiers",
+        "Card 17",
+        "Next proposal",
+        &format!("Propose {} to verifier", proposal.code),
+        " holds if ",
+        "Remaining codes (",
+        "History",
+        "✓ (",
+        "q quit",
+    ] {
+        assert!(screen.contains(text), "{text} missing from {screen}");
+    }
+}